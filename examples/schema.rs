@@ -3,7 +3,10 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use crowd_funding::msg::{ExecuteMsg, GetProjectInfoResponse, InstantiateMsg, QueryMsg};
+use crowd_funding::factory;
+use crowd_funding::msg::{
+    ExecuteMsg, GetProjectInfoResponse, InstantiateMsg, MigrateMsg, QueryMsg,
+};
 use crowd_funding::state::ProjectInfo;
 
 fn main() {
@@ -15,6 +18,17 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(ProjectInfo), &out_dir);
     export_schema(&schema_for!(GetProjectInfoResponse), &out_dir);
+
+    out_dir.push("factory");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(factory::msg::InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(factory::msg::ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(factory::msg::QueryMsg), &out_dir);
+    export_schema(&schema_for!(factory::msg::GetConfigResponse), &out_dir);
+    export_schema(&schema_for!(factory::msg::ListCampaignsResponse), &out_dir);
 }
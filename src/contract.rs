@@ -1,61 +1,129 @@
-#[cfg(not(feature = "library"))]
+#[cfg(not(any(feature = "library", feature = "factory")))]
 use cosmwasm_std::{entry_point};
 use cosmwasm_std::{
-    to_binary, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Addr, Uint128,
+    from_binary, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, QuerierWrapper, Response, StdError, StdResult, Storage, WasmMsg, Addr,
+    Uint128,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20Contract, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw721_base::{ExecuteMsg as Cw721ExecuteMsg, MintMsg};
+use cw_storage_plus::Bound;
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
+use semver::Version;
 
 use crate::error::ContractError;
 use crate::msg::{
-    ExecuteMsg, GetContributionResponse, GetProjectInfoResponse, InstantiateMsg, QueryMsg, Token
+    AllowlistProof, AssetResponse, BeneficiaryMsg, ContributionResponse, ContributorCountResponse,
+    ExecuteMsg, GetContributionAllowanceResponse, GetContributionResponse,
+    GetLegacyContributionResponse, GetProjectInfoResponse, GetReceiptResponse,
+    GetRewardTiersResponse, InstantiateMsg, ListContributionsResponse, ListProjectsResponse,
+    ListProposalsResponse, ListReferralsResponse, MatchingPoolResponse, MigrateMsg, PlatformFeeMsg,
+    ProjectMsg, ProposalResponse, QueryMsg, ReceiptMetadata, ReceiveMsg, ReferralResponse,
+    RewardTierResponse, RoundMatchesResponse, RoundResponse, Token, TokenAmount, VestingResponse,
+};
+use crate::migrations;
+use crate::state::{
+    referrals, AcceptedAsset, AssetAmount, Beneficiary, Config, Governance, MatchingPool, Milestone,
+    PendingOwner, PlatformFee, ProjectInfo, ProjectMatch, Proposal, ProposalStatus, Receipt,
+    Referral, RewardTier, Round, RoundStatus, Status, TokenConfig, Vesting, ACCEPTED_ASSETS,
+    ASSETS_RAISED, ASSET_CONTRIBUTIONS, ATTESTATIONS, BENEFICIARIES, BACKER_TIERS, CONFIG,
+    CONTRIBUTIONS, CONTRIBUTOR_COUNT, GOVERNANCES, MATCHED, MATCHING_POOLS, MERKLE_ROOTS,
+    MILESTONES, PENDING_OWNERS, PROJECTS, PROJECT_COUNT, PROPOSALS, PROPOSAL_COUNT, RECEIPTS,
+    RECEIPT_COUNT, RECEIPT_NFTS, RECEIPT_VOTES, REFERRERS, REFUNDS, REWARD_TIERS, ROUNDS,
+    ROUND_COUNT, ROUND_FUNDS, ROUND_MATCHED, ROUND_SQRT_SUMS, SQRT_SUMS, TOKEN_CONFIGS, VESTINGS,
+    VOTES,
 };
-use crate::state::{ProjectInfo, Status, TokenConfig, CONTRIBUTIONS, PROJECT_INFO, TOKEN_CONFIG};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crowd-funding";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-#[cfg_attr(not(feature = "library"), entry_point)]
+// how long an ownership proposal stays open when no expiry is given
+const OWNERSHIP_PROPOSAL_EXPIRY: u64 = 7 * 24 * 60 * 60;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[cfg_attr(not(any(feature = "library", feature = "factory")), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    match msg.token {
-        Token::Native { denom } => {
-            TOKEN_CONFIG.save(deps.storage, &TokenConfig::Native{
-                denom,
-            })?;
-        },
-        Token::CW20 { addr} => {
-            TOKEN_CONFIG.save(deps.storage, &TokenConfig::CW20{
-                addr,
-            })?;
-        },
-    }
-    
-    let project_info = ProjectInfo {
-        title: msg.title,
-        description: msg.description,
-        project_owner: info.sender.clone(),
-        target_amount: msg.target_amount,
-        end_time: msg.end_time,
-        current_amount: Uint128::zero(),
-        status: Status::Ongoing,
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender.clone(),
     };
 
+    let platform_fee = platform_fee_of(deps.api, msg.platform_fee)?;
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    PROJECT_INFO.save(deps.storage, &project_info)?;
+    CONFIG.save(deps.storage, &Config { admin, platform_fee })?;
+    PROJECT_COUNT.save(deps.storage, &0)?;
 
-    Ok(Response::new()
+    let mut res = Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("owner", info.sender))
+        .add_attribute("owner", info.sender.clone());
+
+    // instantiate msg can carry the first project
+    if let Some(project) = msg.project {
+        let project_owner = match msg.project_owner {
+            Some(project_owner) => deps.api.addr_validate(&project_owner)?,
+            None => info.sender,
+        };
+        let project_id = create_project(deps, project_owner, project)?;
+        res = res.add_attribute("project_id", project_id.to_string());
+    }
+
+    Ok(res)
+}
+
+#[cfg_attr(not(any(feature = "library", feature = "factory")), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            contract: stored.contract,
+        });
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    let new_version = parse_version(CONTRACT_VERSION)?;
+    if new_version < stored_version {
+        return Err(ContractError::CannotDowngrade {
+            from: stored_version.to_string(),
+            to: new_version.to_string(),
+        });
+    }
+
+    // bring state up to date one layout at a time
+    if stored_version < Version::new(0, 2, 0) {
+        migrations::v0_1::migrate(deps.storage, deps.api, &deps.querier, &env, msg)?;
+    }
+    if stored_version < Version::new(0, 3, 0) {
+        migrations::v0_2::migrate(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    version.parse().map_err(|_| ContractError::InvalidVersion {
+        version: version.into(),
+    })
+}
+
+#[cfg_attr(not(any(feature = "library", feature = "factory")), entry_point)]
 pub fn execute(
     deps: DepsMut,
     env: Env,
@@ -63,76 +131,375 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
+        ExecuteMsg::CreateProject(msg) => try_create_project(deps, info, *msg),
         // contribute msg only when token config set to native
-        ExecuteMsg::Contribute {} => try_contribute(deps, env, info),
+        ExecuteMsg::Contribute {
+            project_id,
+            tier,
+            proof,
+            referrer,
+        } => try_contribute(deps, env, info, project_id, tier, proof, referrer),
         // recieve msg only when token config set to cw20.
         // To contribute, user need to send cw20 token to this contract address, then recieve msg is hooked.
         ExecuteMsg::Receive(msg) => try_recieve_and_contribute(deps, env, info, msg),
-        ExecuteMsg::Withdraw {} => try_withdraw(deps, env, info),
-        ExecuteMsg::Refund {} => try_refund(deps, env, info),
+        ExecuteMsg::ProposeNewOwner {
+            project_id,
+            addr,
+            expires,
+        } => try_propose_new_owner(deps, env, info, project_id, addr, expires),
+        ExecuteMsg::AcceptOwnership { project_id } => {
+            try_accept_ownership(deps, env, info, project_id)
+        }
+        ExecuteMsg::CancelOwnershipProposal { project_id } => {
+            try_cancel_ownership_proposal(deps, info, project_id)
+        }
+        ExecuteMsg::UpdateBeneficiaries {
+            project_id,
+            beneficiaries,
+        } => try_update_beneficiaries(deps, env, info, project_id, beneficiaries),
+        ExecuteMsg::Withdraw { project_id } => try_withdraw(deps, env, info, project_id),
+        ExecuteMsg::ClaimVested { project_id } => try_claim_vested(deps, env, info, project_id),
+        ExecuteMsg::CreateProposal {
+            project_id,
+            amount,
+            purpose,
+        } => try_create_proposal(deps, env, info, project_id, amount, purpose),
+        ExecuteMsg::Vote {
+            project_id,
+            proposal_id,
+            approve,
+            receipts,
+        } => try_vote(deps, env, info, (project_id, proposal_id), approve, receipts),
+        ExecuteMsg::ExecuteProposal {
+            project_id,
+            proposal_id,
+        } => try_execute_proposal(deps, env, project_id, proposal_id),
+        ExecuteMsg::ReleaseMilestone { project_id, index } => {
+            try_release_milestone(deps, env, info, project_id, index)
+        }
+        ExecuteMsg::Abandon { project_id } => try_abandon(deps, env, info, project_id),
+        ExecuteMsg::Cancel { project_id } => try_cancel(deps, info, project_id),
+        ExecuteMsg::DepositMatchingFunds { project_id, ratio } => {
+            try_deposit_matching_funds(deps, env, info, project_id, ratio)
+        }
+        ExecuteMsg::ReclaimMatchingFunds { project_id } => {
+            try_reclaim_matching_funds(deps, env, info, project_id)
+        }
+        ExecuteMsg::CreateRound {
+            token,
+            project_ids,
+            attestor,
+            end_time,
+        } => try_create_round(deps, info, token, project_ids, attestor, end_time),
+        ExecuteMsg::FundRound { round_id } => try_fund_round(deps, info, round_id),
+        ExecuteMsg::AttestContributors {
+            round_id,
+            addresses,
+        } => try_attest_contributors(deps, info, round_id, addresses),
+        ExecuteMsg::TallyRound { round_id } => try_tally_round(deps, env, info, round_id),
+        ExecuteMsg::DistributeRound { round_id } => try_distribute_round(deps, info, round_id),
+        ExecuteMsg::CancelRound { round_id } => try_cancel_round(deps, info, round_id),
+        ExecuteMsg::ReclaimRoundFunds { round_id } => {
+            try_reclaim_round_funds(deps, info, round_id)
+        }
+        ExecuteMsg::Unpledge {
+            project_id,
+            amount,
+            asset,
+        } => try_unpledge(deps, env, info, project_id, amount, asset.unwrap_or_default()),
+        ExecuteMsg::Refund {
+            project_id,
+            receipt,
+        } => try_refund(deps, env, info, project_id, receipt),
+        ExecuteMsg::ReassignLegacyContribution {
+            project_id,
+            recipient,
+            amount,
+        } => try_reassign_legacy_contribution(deps, info, project_id, recipient, amount),
     }
 }
 
-pub fn try_contribute(
+pub fn try_create_project(
     deps: DepsMut,
-    env: Env,
     info: MessageInfo,
+    msg: ProjectMsg,
 ) -> Result<Response, ContractError> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
+    let project_id = create_project(deps, info.sender.clone(), msg)?;
 
-    let config_denom: String;
-    match token_config {
-        TokenConfig::Native{ denom } => {
-            config_denom = denom;
+    Ok(Response::new()
+        .add_attribute("action", "create_project")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("owner", info.sender))
+}
+
+// stores a new project under the next id, sender of the msg becomes project owner
+fn create_project(
+    deps: DepsMut,
+    project_owner: Addr,
+    msg: ProjectMsg,
+) -> Result<u64, ContractError> {
+    if let Some(start_time) = msg.start_time {
+        if msg.end_time <= start_time {
+            return Err(ContractError::InvalidStartTime {});
+        }
+    }
+    if let Some(max_amount) = msg.max_amount {
+        if max_amount < msg.target_amount {
+            return Err(ContractError::InvalidMaxAmount {});
+        }
+    }
+    if msg.unpledge_penalty > 100 {
+        return Err(ContractError::InvalidUnpledgePenalty {});
+    }
+    if msg.referral_reward > 100 {
+        return Err(ContractError::InvalidReferralReward {});
+    }
+    if let (Some(min), Some(max)) = (msg.min_contribution, msg.max_contribution_per_address) {
+        if max < min {
+            return Err(ContractError::InvalidContributionLimits {});
+        }
+    }
+    if let Some(vesting) = &msg.vesting {
+        // milestones already pace the payout
+        if !msg.milestones.is_empty() {
+            return Err(ContractError::MilestonesConfigured {});
+        }
+        if vesting.duration == 0 || vesting.duration < vesting.cliff {
+            return Err(ContractError::InvalidVesting {});
+        }
+    }
+    let beneficiaries = beneficiaries_of(deps.api, msg.beneficiaries)?;
+    if let Some(governance) = &msg.governance {
+        if !msg.milestones.is_empty() || msg.vesting.is_some() {
+            return Err(ContractError::GovernanceExclusive {});
+        }
+        if Decimal::one() < governance.quorum
+            || Decimal::one() < governance.threshold
+            || governance.voting_period == 0
+        {
+            return Err(ContractError::InvalidGovernance {});
+        }
+    }
+
+    let storage = deps.storage;
+    let project_id = PROJECT_COUNT.load(storage)? + 1;
+    PROJECT_COUNT.save(storage, &project_id)?;
+
+    if !msg.milestones.is_empty() {
+        let total: u64 = msg.milestones.iter().map(|m| m.percentage).sum();
+        if total != 100 || msg.milestones.iter().any(|m| m.percentage == 0) {
+            return Err(ContractError::InvalidMilestones {});
+        }
+        let milestones: Vec<Milestone> = msg
+            .milestones
+            .into_iter()
+            .map(|m| Milestone {
+                title: m.title,
+                percentage: m.percentage,
+                unlock_time: m.unlock_time,
+                released: false,
+            })
+            .collect();
+        MILESTONES.save(storage, project_id, &milestones)?;
+    }
+
+    if !msg.reward_tiers.is_empty() {
+        let reward_tiers: Vec<RewardTier> = msg
+            .reward_tiers
+            .into_iter()
+            .map(|t| RewardTier {
+                name: t.name,
+                min_amount: t.min_amount,
+                max_backers: t.max_backers,
+                backers: 0,
+            })
+            .collect();
+        REWARD_TIERS.save(storage, project_id, &reward_tiers)?;
+    }
+
+    if let Some(vesting) = msg.vesting {
+        let vesting = Vesting {
+            cliff: vesting.cliff,
+            duration: vesting.duration,
+            start_time: None,
+            offset: Uint128::zero(),
+            amount: Uint128::zero(),
+            claimed: Uint128::zero(),
+        };
+        VESTINGS.save(storage, project_id, &vesting)?;
+    }
+
+    if !beneficiaries.is_empty() {
+        BENEFICIARIES.save(storage, project_id, &beneficiaries)?;
+    }
+
+    if let Some(governance) = msg.governance {
+        let governance = Governance {
+            quorum: governance.quorum,
+            threshold: governance.threshold,
+            voting_period: governance.voting_period,
+        };
+        GOVERNANCES.save(storage, project_id, &governance)?;
+    }
+
+    if let Some(merkle_root) = msg.merkle_root {
+        decode_hash(&merkle_root)?;
+        MERKLE_ROOTS.save(storage, project_id, &merkle_root)?;
+    }
+
+    if let Some(receipt_nft) = msg.receipt_nft {
+        // receipts are refunded in main token
+        if !msg.accepted_assets.is_empty() {
+            return Err(ContractError::ReceiptsSingleAsset {});
+        }
+        let receipt_nft = deps.api.addr_validate(&receipt_nft)?;
+        RECEIPT_NFTS.save(storage, project_id, &receipt_nft)?;
+    }
+
+    let token_config = token_config_of(msg.token);
+    TOKEN_CONFIGS.save(storage, project_id, &token_config)?;
+
+    if !msg.accepted_assets.is_empty() {
+        if msg.accepted_assets.iter().any(|a| a.rate.is_zero()) {
+            return Err(ContractError::InvalidConversionRate {});
         }
-        TokenConfig::CW20{ addr: _ } => {
-            return Err(ContractError::CustomError {
-                val: "contribute msg is available only when token config set to Native".into(),
+        // a repeated token could never be contributed, the first one always matches
+        let mut tokens = vec![token_config];
+        let mut accepted_assets: Vec<AcceptedAsset> = vec![];
+        for asset in msg.accepted_assets {
+            if let Token::CW20 { addr } = &asset.token {
+                deps.api.addr_validate(addr.as_str())?;
+            }
+            let token = token_config_of(asset.token);
+            if tokens.contains(&token) {
+                return Err(ContractError::DuplicateAsset {});
+            }
+            tokens.push(token.clone());
+            accepted_assets.push(AcceptedAsset {
+                token,
+                rate: asset.rate,
             });
         }
+        ACCEPTED_ASSETS.save(storage, project_id, &accepted_assets)?;
     }
 
-    let mut project_info = PROJECT_INFO.load(deps.storage)?;
-    if info.sender == project_info.project_owner {
-        return Err(ContractError::CustomError {
-            val: "project owner cannot contribute".into(),
-        });
+    let project_info = ProjectInfo {
+        title: msg.title,
+        description: msg.description,
+        project_owner,
+        target_amount: msg.target_amount,
+        max_amount: msg.max_amount,
+        min_contribution: msg.min_contribution,
+        max_contribution_per_address: msg.max_contribution_per_address,
+        unpledge_penalty: msg.unpledge_penalty,
+        referral_reward: msg.referral_reward,
+        start_time: msg.start_time,
+        end_time: msg.end_time,
+        current_amount: Uint128::zero(),
+        withdrawn_amount: Uint128::zero(),
+        fee_amount: Uint128::zero(),
+        refunded_amount: Uint128::zero(),
+        status: Status::Ongoing,
+    };
+    PROJECTS.save(storage, project_id, &project_info)?;
+
+    Ok(project_id)
+}
+
+// validates platform fee, shared with the factory which sets it for every campaign
+pub fn platform_fee_of(
+    api: &dyn Api,
+    platform_fee: Option<PlatformFeeMsg>,
+) -> Result<Option<PlatformFee>, ContractError> {
+    let platform_fee = match platform_fee {
+        Some(platform_fee) => platform_fee,
+        None => return Ok(None),
+    };
+    if Decimal::one() < platform_fee.fee {
+        return Err(ContractError::InvalidPlatformFee {});
     }
+    Ok(Some(PlatformFee {
+        treasury: api.addr_validate(&platform_fee.treasury)?,
+        fee: platform_fee.fee,
+    }))
+}
 
-    let now: u64 = env.block.time.clone().seconds();
-    if project_info.end_time <= now {
-        return Err(ContractError::CustomError {
-            val: "end_time already exceeded".into(),
-        });
+// validates beneficiary shares, empty means project owner gets everything
+fn beneficiaries_of(
+    api: &dyn Api,
+    beneficiaries: Vec<BeneficiaryMsg>,
+) -> Result<Vec<Beneficiary>, ContractError> {
+    if beneficiaries.is_empty() {
+        return Ok(vec![]);
     }
+    let total: u64 = beneficiaries.iter().map(|b| b.percentage).sum();
+    if total != 100 || beneficiaries.iter().any(|b| b.percentage == 0) {
+        return Err(ContractError::InvalidBeneficiaries {});
+    }
+    beneficiaries
+        .into_iter()
+        .map(|b| {
+            Ok(Beneficiary {
+                address: api.addr_validate(&b.address)?,
+                percentage: b.percentage,
+            })
+        })
+        .collect()
+}
 
-    // only the same denom is acceptable
-    let contribute = info
-        .funds
-        .iter()
-        .find(|x| x.denom == config_denom)
-        .ok_or_else(|| ContractError::CustomError {
-            val: format!("Only denom {} accepted", &config_denom),
-        })?;
+fn load_project(storage: &dyn Storage, project_id: u64) -> Result<ProjectInfo, ContractError> {
+    PROJECTS
+        .may_load(storage, project_id)?
+        .ok_or(ContractError::ProjectNotFound { project_id })
+}
 
-    let contributed_amount = contribute.amount;
+pub fn try_contribute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    tier: Option<u32>,
+    proof: Option<AllowlistProof>,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    load_project(deps.storage, project_id)?;
+    let assets = project_assets(deps.storage, project_id)?;
 
-    // update current amount
-    project_info.current_amount += contributed_amount;
-    if project_info.target_amount <= project_info.current_amount
-        && project_info.status != Status::Succeeded
-    {
-        project_info.status = Status::Succeeded;
+    let denoms: Vec<&str> = assets
+        .iter()
+        .filter_map(|a| match &a.token {
+            TokenConfig::Native { denom } => Some(denom.as_str()),
+            TokenConfig::CW20 { addr: _ } => None,
+        })
+        .collect();
+    if denoms.is_empty() {
+        return Err(ContractError::Cw20Only {});
     }
-    PROJECT_INFO.save(deps.storage, &project_info)?;
 
-    // update contribution map
-    CONTRIBUTIONS.update(deps.storage, &info.sender, |balance| -> StdResult<_> {
-        Ok(balance.unwrap_or_default() + contributed_amount)
-    })?;
+    // exactly one coin of an accepted denom
+    let wrong_denom = || ContractError::WrongDenom {
+        expected: denoms.join(", "),
+    };
+    let contribute = single_coin(&info.funds)?.ok_or_else(wrong_denom)?;
+    let asset = assets
+        .iter()
+        .position(|a| a.token == TokenConfig::Native { denom: contribute.denom.clone() })
+        .ok_or_else(wrong_denom)? as u32;
+
+    let contribution = Contribution {
+        project_id,
+        asset,
+        amount: contribute.amount,
+        tier,
+        proof,
+        referrer: referrer.map(|r| deps.api.addr_validate(&r)).transpose()?,
+    };
+    let msgs = add_contribution(deps.storage, &env, &info.sender, contribution)?;
 
     let res = Response::new()
+        .add_messages(msgs)
         .add_attribute("action", "contribute")
+        .add_attribute("project_id", project_id.to_string())
         .add_attribute("denom", &contribute.denom)
         .add_attribute("amount", contribute.amount);
 
@@ -140,296 +507,5144 @@ pub fn try_contribute(
 }
 
 pub fn try_recieve_and_contribute (deps: DepsMut, env: Env, info: MessageInfo, wrapped: Cw20ReceiveMsg) -> Result<Response, ContractError> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
-
-    let config_cw20_addr: Addr;
-    match token_config {
-        TokenConfig::Native{ denom: _ } => {
-            return Err(ContractError::CustomError {
-                val: "contribute msg is available only when token config set to Native".into(),
-            });
+    // wrapped.msg tells which project the sent tokens are for
+    let (project_id, tier, proof, referrer) = match from_binary(&wrapped.msg)? {
+        ReceiveMsg::Contribute {
+            project_id,
+            tier,
+            proof,
+            referrer,
+        } => (project_id, tier, proof, referrer),
+        ReceiveMsg::DepositMatchingFunds { project_id, ratio } => {
+            let sponsor = deps.api.addr_validate(&wrapped.sender)?;
+            let token = TokenConfig::CW20 { addr: info.sender };
+            let deposit = (token, wrapped.amount);
+            return deposit_matching_funds(deps.storage, &env, project_id, sponsor, deposit, ratio);
         }
-        TokenConfig::CW20{ addr } => {
-            config_cw20_addr = addr;
+        ReceiveMsg::FundRound { round_id } => {
+            let funder = deps.api.addr_validate(&wrapped.sender)?;
+            let token = TokenConfig::CW20 { addr: info.sender };
+            return fund_round(deps.storage, round_id, funder, (token, wrapped.amount));
         }
-    }
+    };
 
-    let mut project_info = PROJECT_INFO.load(deps.storage)?;
-    // wrapped.sender is original msg executor
-    if wrapped.sender == project_info.project_owner {
-        return Err(ContractError::CustomError {
-            val: "project owner cannot contribute".into(),
-        });
-    }
+    load_project(deps.storage, project_id)?;
+    let assets = project_assets(deps.storage, project_id)?;
 
-    let now: u64 = env.block.time.clone().seconds();
-    if project_info.end_time <= now {
-        return Err(ContractError::CustomError {
-            val: "end_time already exceeded".into(),
-        });
+    let cw20_addrs: Vec<&Addr> = assets
+        .iter()
+        .filter_map(|a| match &a.token {
+            TokenConfig::Native { denom: _ } => None,
+            TokenConfig::CW20 { addr } => Some(addr),
+        })
+        .collect();
+    if cw20_addrs.is_empty() {
+        return Err(ContractError::NativeOnly {});
     }
 
     // info.sender is cw20 contract address
     // only configured cw20 token acceptable
-    if info.sender != config_cw20_addr {
-        return Err(ContractError::CustomError {
-            val: "wrong cw20 token recieved".into(),
-        });
-    }
+    let asset = assets
+        .iter()
+        .position(|a| a.token == TokenConfig::CW20 { addr: info.sender.clone() })
+        .ok_or_else(|| ContractError::WrongCw20Token {
+            expected: cw20_addrs[0].clone(),
+        })? as u32;
+
+    // wrapped.sender is original msg executor, contribution is credited to this address
+    let contributor = deps.api.addr_validate(&wrapped.sender)?;
 
     // wrapped.amount is amount of cw20 which is sent
     let contributed_amount = wrapped.amount;
 
-    // update current amount
-    project_info.current_amount += contributed_amount;
-    if project_info.target_amount <= project_info.current_amount
-        && project_info.status != Status::Succeeded
-    {
-        project_info.status = Status::Succeeded;
-    }
-    PROJECT_INFO.save(deps.storage, &project_info)?;
-
-    // update contribution map
-    CONTRIBUTIONS.update(deps.storage, &info.sender, |balance| -> StdResult<_> {
-        Ok(balance.unwrap_or_default() + contributed_amount)
-    })?;
+    let contribution = Contribution {
+        project_id,
+        asset,
+        amount: contributed_amount,
+        tier,
+        proof,
+        referrer: referrer.map(|r| deps.api.addr_validate(&r)).transpose()?,
+    };
+    let msgs = add_contribution(deps.storage, &env, &contributor, contribution)?;
 
     let res = Response::new()
+        .add_messages(msgs)
         .add_attribute("action", "contribute")
-        .add_attribute("cw20_address", &config_cw20_addr)
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("contributor", contributor)
+        .add_attribute("cw20_address", info.sender)
         .add_attribute("amount", contributed_amount);
 
     Ok(res)
 }
 
-pub fn try_withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
+// contribution being credited to a project, amount is in units of the asset
+struct Contribution {
+    project_id: u64,
+    asset: u32,
+    amount: Uint128,
+    tier: Option<u32>,
+    proof: Option<AllowlistProof>,
+    referrer: Option<Addr>,
+}
 
-    let project_info = PROJECT_INFO.load(deps.storage)?;
-    if info.sender != project_info.project_owner {
-        return Err(ContractError::CustomError {
-            val: "only project owner can withdraw".into(),
-        });
+// common part of native and cw20 contribution, token is already checked by the caller.
+// returns messages to send along, e.g. receipt mint
+fn add_contribution(
+    storage: &mut dyn Storage,
+    env: &Env,
+    contributor: &Addr,
+    contribution: Contribution,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let project_id = contribution.project_id;
+    let mut project_info = load_project(storage, project_id)?;
+    if contributor == &project_info.project_owner {
+        return Err(ContractError::OwnerCannotContribute {});
+    }
+    if project_info.status == Status::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    if let Some(referrer) = &contribution.referrer {
+        if referrer == contributor || referrer == &project_info.project_owner {
+            return Err(ContractError::InvalidReferrer {});
+        }
     }
 
-    let now: u64 = env.block.time.clone().seconds();
-    if now < project_info.end_time {
-        return Err(ContractError::CustomError {
-            val: "project not ended".into(),
-        });
+    let now: u64 = env.block.time.seconds();
+    if let Some(start_time) = project_info.start_time {
+        if now < start_time {
+            return Err(ContractError::ProjectNotStarted { start_time });
+        }
+    }
+    if project_info.end_time <= now {
+        return Err(ContractError::ProjectEnded {});
+    }
+    if is_closed(&project_info, now) {
+        return Err(ContractError::HardCapReached {});
     }
+    let allowlist_cap = check_allowlist(storage, project_id, contributor, contribution.proof)?;
 
-    if project_info.status != Status::Succeeded {
-        return Err(ContractError::CustomError {
-            val: "project not succeeded".into(),
-        });
+    let asset = project_assets(storage, project_id)?.swap_remove(contribution.asset as usize);
+    let mut accepted = AssetAmount {
+        amount: contribution.amount,
+        credited: contribution.amount * asset.rate,
+    };
+    if accepted.credited.is_zero() {
+        return Err(ContractError::ZeroContribution {});
     }
 
-    let msg: CosmosMsg;
-    match token_config {
-        TokenConfig::Native{ denom } => {
-            msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: project_info.project_owner.into(),
-                amount: vec![Coin::new(
-                    project_info.current_amount.into(),
-                    denom,
-                )],
-            });
-        },
-        TokenConfig::CW20{ addr } => {
-            let cw20 = Cw20Contract(addr);
-            msg = cw20.call(Cw20ExecuteMsg::Transfer {
-                recipient: project_info.project_owner.into(),
-                amount: project_info.current_amount.into(),
-            })?;
-        },
+    // only what fits under the hard cap is accepted, the rest goes back right away
+    let mut msgs = vec![];
+    if let Some(max_amount) = project_info.max_amount {
+        let room = max_amount - project_info.current_amount;
+        if room < accepted.credited {
+            accepted.amount = room.multiply_ratio(Decimal::one().atomics(), asset.rate.atomics());
+            accepted.credited = accepted.amount * asset.rate;
+            // room left is below one unit of this asset
+            if accepted.credited.is_zero() {
+                return Err(ContractError::HardCapReached {});
+            }
+            let excess = contribution.amount - accepted.amount;
+            msgs.push(transfer_msg(asset.token, contributor, excess)?);
+        }
     }
+    let contributed_amount = accepted.credited;
 
-    Ok(Response::new()
-        .add_message(msg))
-}
+    let contributed_total = CONTRIBUTIONS
+        .may_load(storage, (project_id, contributor))?
+        .unwrap_or_default()
+        + contributed_amount;
+    check_contribution_limits(&project_info, contributed_total, contributed_amount)?;
+    if let Some(cap) = allowlist_cap {
+        if cap < contributed_total {
+            return Err(ContractError::AboveMaxContribution {
+                remaining: cap.saturating_sub(contributed_total - contributed_amount),
+            });
+        }
+    }
 
-pub fn try_refund(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
-    let mut project_info = PROJECT_INFO.load(deps.storage)?;
+    // receipts may change hands, so in receipt projects a tier goes with the receipt
+    // of this contribution rather than with the contributor
+    let receipt_nft = RECEIPT_NFTS.may_load(storage, project_id)?;
+    if let Some(index) = contribution.tier {
+        let (tier_holder, amount) = match receipt_nft {
+            Some(_) => (None, contributed_amount),
+            None => (Some(contributor), contributed_total),
+        };
+        select_reward_tier(storage, project_id, tier_holder, index, amount)?;
+    }
 
-    let now: u64 = env.block.time.clone().seconds();
-    if now < project_info.end_time {
-        return Err(ContractError::CustomError {
-            val: "project not ended".into(),
-        });
+    let matched_amount =
+        match_contribution(storage, &project_info, project_id, contributed_amount)?;
+    if !matched_amount.is_zero() {
+        MATCHED.update(storage, (project_id, contributor), |matched| -> StdResult<_> {
+            Ok(matched.unwrap_or_default() + matched_amount)
+        })?;
     }
 
-    if project_info.current_amount < project_info.target_amount {
-        project_info.status = Status::Failed;
-    } else {
+    // update current amount, matched funds count toward the target too
+    project_info.current_amount += contributed_amount + matched_amount;
+    if project_info.target_amount <= project_info.current_amount
+        && project_info.status != Status::Succeeded
+    {
         project_info.status = Status::Succeeded;
     }
+    PROJECTS.save(storage, project_id, &project_info)?;
 
-    if project_info.status != Status::Failed {
-        return Err(ContractError::CustomError {
-            val: "project not failed".into(),
-        });
+    // update contribution map
+    save_contribution(storage, project_id, contributor, contributed_total)?;
+
+    // a backer stays with the referrer they first came with,
+    // what they contributed before it goes with the referrer too
+    let key = (project_id, contributor);
+    let referred = match REFERRERS.may_load(storage, key)? {
+        Some(referrer) => Some((referrer, contributed_amount)),
+        None => match contribution.referrer {
+            Some(referrer) => {
+                REFERRERS.save(storage, key, &referrer)?;
+                Some((referrer, contributed_total))
+            }
+            None => None,
+        },
+    };
+    if let Some((referrer, referred)) = referred {
+        update_referred(storage, project_id, &referrer, |amount| amount + referred)?;
     }
 
-    let result = CONTRIBUTIONS.may_load(deps.storage, &info.sender)?;
-    if result.is_none() {
-        return Err(ContractError::CustomError {
-            val: "no contribution found".into(),
-        })
+    // main token part follows from current amount, other assets are tracked on their own
+    if contribution.asset > 0 {
+        let add = |amount: Option<AssetAmount>| -> StdResult<_> {
+            let amount = amount.unwrap_or_default();
+            Ok(AssetAmount {
+                amount: amount.amount + accepted.amount,
+                credited: amount.credited + accepted.credited,
+            })
+        };
+        ASSETS_RAISED.update(storage, (project_id, contribution.asset), add)?;
+        ASSET_CONTRIBUTIONS.update(storage, (project_id, contributor, contribution.asset), add)?;
     }
-    // result is not None here
-    let refund_amount = result.unwrap();
 
-    let msg: CosmosMsg;
-    match token_config {
-        TokenConfig::Native{ denom } => {
-            msg = CosmosMsg::Bank(BankMsg::Send {
-                to_address: info.sender.into(),
-                amount: vec![Coin::new(
-                    refund_amount.into(),
-                    denom,
-                )],
-            });
-        },
-        TokenConfig::CW20{ addr } => {
-            let cw20 = Cw20Contract(addr);
-            msg = cw20.call(Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.into(),
-                amount: refund_amount.into(),
-            })?;
-        },
+    if let Some(receipt_nft) = receipt_nft {
+        let receipt = Receipt {
+            backer: contributor.clone(),
+            amount: contributed_amount,
+            tier: contribution.tier,
+            refunded: false,
+        };
+        msgs.push(mint_receipt_msg(storage, project_id, receipt_nft, receipt)?);
     }
 
-    Ok(Response::new()
-        .add_message(msg))
+    Ok(msgs)
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetProjectInfo {} => to_binary(&query_project_info(deps, env)?),
-        QueryMsg::GetContribution { address } => to_binary(&query_contribution(deps, address)?),
+// takes the sponsor share of a contribution out of the matching pool,
+// project_info is still without the contribution
+fn match_contribution(
+    storage: &mut dyn Storage,
+    project_info: &ProjectInfo,
+    project_id: u64,
+    contributed_amount: Uint128,
+) -> StdResult<Uint128> {
+    let mut pool = match MATCHING_POOLS.may_load(storage, project_id)? {
+        Some(pool) => pool,
+        None => return Ok(Uint128::zero()),
+    };
+
+    let mut matched_amount = (contributed_amount * pool.ratio).min(pool.cap - pool.matched);
+    if let Some(max_amount) = project_info.max_amount {
+        let room = max_amount - project_info.current_amount - contributed_amount;
+        matched_amount = matched_amount.min(room);
     }
+    if !matched_amount.is_zero() {
+        pool.matched += matched_amount;
+        MATCHING_POOLS.save(storage, project_id, &pool)?;
+    }
+    Ok(matched_amount)
 }
 
-fn query_project_info(deps: Deps, env: Env) -> StdResult<GetProjectInfoResponse> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
-    let token = match token_config {
-        TokenConfig::Native{ denom } => {
-            Token::Native{denom}
-        },
-        TokenConfig::CW20{ addr } => {
-            Token::CW20{addr}
+// records contribution of an address, counting it when it is new to the project,
+// nothing left removes it
+fn save_contribution(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+    amount: Uint128,
+) -> StdResult<()> {
+    if amount.is_zero() {
+        return remove_contribution(storage, project_id, contributor);
+    }
+    let key = (project_id, contributor);
+    let previous = CONTRIBUTIONS.may_load(storage, key)?;
+    if previous.is_none() {
+        CONTRIBUTOR_COUNT.update(storage, project_id, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + 1)
+        })?;
+    }
+    update_sqrt_sums(storage, project_id, contributor, previous.unwrap_or_default(), amount)?;
+    CONTRIBUTIONS.save(storage, key, &amount)
+}
+
+fn remove_contribution(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+) -> StdResult<()> {
+    let key = (project_id, contributor);
+    if let Some(previous) = CONTRIBUTIONS.may_load(storage, key)? {
+        CONTRIBUTOR_COUNT.update(storage, project_id, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default().saturating_sub(1))
+        })?;
+        update_sqrt_sums(storage, project_id, contributor, previous, Uint128::zero())?;
+        CONTRIBUTIONS.remove(storage, key);
+    }
+    Ok(())
+}
+
+// keeps the quadratic funding sums of a project in step with a changed contribution,
+// attested rounds only count contributors their attestor vouched for
+fn update_sqrt_sums(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+    previous: Uint128,
+    amount: Uint128,
+) -> StdResult<()> {
+    let previous = Uint128::new(isqrt(previous.u128()));
+    let amount = Uint128::new(isqrt(amount.u128()));
+    if previous == amount {
+        return Ok(());
+    }
+    let update =
+        |sum: Option<Uint128>| -> StdResult<_> { Ok(sum.unwrap_or_default() + amount - previous) };
+    SQRT_SUMS.update(storage, project_id, update)?;
+    let round_ids = ROUND_SQRT_SUMS
+        .prefix(project_id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for round_id in round_ids {
+        if ATTESTATIONS.has(storage, (round_id, contributor)) {
+            ROUND_SQRT_SUMS.update(storage, (project_id, round_id), update)?;
         }
-    };
-    let mut project_info = PROJECT_INFO.load(deps.storage)?;
-    let now: u64 = env.block.time.clone().seconds();
-    if project_info.end_time < now && project_info.current_amount < project_info.target_amount {
-        project_info.status = Status::Failed;
     }
+    Ok(())
+}
 
-    Ok(GetProjectInfoResponse {
-        title: project_info.title,
-        description: project_info.description,
-        project_owner: project_info.project_owner,
-        token: token,
-        target_amount: project_info.target_amount,
-        end_time: project_info.end_time,
-        current_amount: project_info.current_amount,
-        status: project_info.status,
-    })
+// round matches are credited to project funds, so payouts wait for every round of the project
+fn check_no_pending_round(storage: &dyn Storage, project_id: u64) -> Result<(), ContractError> {
+    let pending = ROUND_SQRT_SUMS
+        .prefix(project_id)
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .transpose()?;
+    match pending {
+        Some(round_id) => Err(ContractError::RoundPending { round_id }),
+        None => Ok(()),
+    }
 }
 
-fn query_contribution(deps: Deps, address: Addr) -> StdResult<GetContributionResponse> {
-    let token_config = TOKEN_CONFIG.load(deps.storage)?;
-    let token = match token_config {
-        TokenConfig::Native{ denom } => {
-            Token::Native{denom}
-        },
-        TokenConfig::CW20{ addr } => {
-            Token::CW20{addr}
+// keeps total of current contributions a referrer brought in
+fn update_referred(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    referrer: &Addr,
+    update: impl FnOnce(Uint128) -> Uint128,
+) -> StdResult<()> {
+    referrals().update(storage, (project_id, referrer), |referral| -> StdResult<_> {
+        let referral = referral.unwrap_or(Referral {
+            project_id,
+            referrer: referrer.clone(),
+            amount: Uint128::zero(),
+            rewarded: Uint128::zero(),
+        });
+        Ok(Referral {
+            amount: update(referral.amount),
+            ..referral
+        })
+    })?;
+    Ok(())
+}
+
+// limits apply to everything the address contributed so far, including this contribution
+fn check_contribution_limits(
+    project_info: &ProjectInfo,
+    contributed_total: Uint128,
+    contributed_amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(min_contribution) = project_info.min_contribution {
+        if contributed_total < min_contribution {
+            return Err(ContractError::BelowMinContribution { min_contribution });
+        }
+    }
+    if let Some(max) = project_info.max_contribution_per_address {
+        if max < contributed_total {
+            return Err(ContractError::AboveMaxContribution {
+                remaining: max - (contributed_total - contributed_amount),
+            });
         }
+    }
+    Ok(())
+}
+
+// merkle tree hashes start with one of these, so a leaf can't pass for an inner node
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
+
+// private projects only take contributions from addresses in the merkle tree,
+// returns the per address cap of the leaf
+fn check_allowlist(
+    storage: &dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+    proof: Option<AllowlistProof>,
+) -> Result<Option<Uint128>, ContractError> {
+    let merkle_root = match MERKLE_ROOTS.may_load(storage, project_id)? {
+        Some(merkle_root) => decode_hash(&merkle_root)?,
+        None => return Ok(None),
     };
+    let proof = proof.ok_or(ContractError::NotAllowlisted {})?;
 
-    let contribution = CONTRIBUTIONS.may_load(deps.storage, &address)?;
-    let contributed_amount = match contribution {
-        Some(amount) => amount,
-        None => Uint128::zero(),
+    let leaf = match proof.cap {
+        Some(cap) => format!("{}:{}", contributor, cap),
+        None => contributor.to_string(),
     };
-    Ok(GetContributionResponse {
-        token: token,
-        amount: contributed_amount,
+    let mut hash: [u8; 32] = Sha256::new()
+        .chain_update([MERKLE_LEAF_PREFIX])
+        .chain_update(leaf.as_bytes())
+        .finalize()
+        .into();
+    for sibling in &proof.proof {
+        let sibling = decode_hash(sibling)?;
+        // pairs are hashed in sorted order, proofs don't need to tell left from right
+        let (first, second) = if hash < sibling {
+            (hash, sibling)
+        } else {
+            (sibling, hash)
+        };
+        hash = Sha256::new()
+            .chain_update([MERKLE_NODE_PREFIX])
+            .chain_update(first)
+            .chain_update(second)
+            .finalize()
+            .into();
+    }
+    if hash != merkle_root {
+        return Err(ContractError::NotAllowlisted {});
+    }
+
+    Ok(proof.cap)
+}
+
+fn decode_hash(hash: &str) -> Result<[u8; 32], ContractError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(ContractError::InvalidMerkleHash {})
+}
+
+// every asset a project accepts, main token first at rate 1
+fn project_assets(storage: &dyn Storage, project_id: u64) -> StdResult<Vec<AcceptedAsset>> {
+    let mut assets = vec![AcceptedAsset {
+        token: TOKEN_CONFIGS.load(storage, project_id)?,
+        rate: Decimal::one(),
+    }];
+    assets.extend(
+        ACCEPTED_ASSETS
+            .may_load(storage, project_id)?
+            .unwrap_or_default(),
+    );
+    Ok(assets)
+}
+
+// splits a main token unit total into amounts of every asset, main token first.
+// load gives tracked amount of the other assets by index
+fn split_assets(
+    storage: &dyn Storage,
+    project_id: u64,
+    total: Uint128,
+    load: impl Fn(u32) -> StdResult<Option<AssetAmount>>,
+) -> StdResult<Vec<(TokenConfig, Uint128)>> {
+    let mut assets = vec![];
+    let mut main_amount = total;
+    for (index, asset) in project_assets(storage, project_id)?.into_iter().enumerate() {
+        if index == 0 {
+            assets.push((asset.token, Uint128::zero()));
+            continue;
+        }
+        let amount = load(index as u32)?.unwrap_or_default();
+        main_amount -= amount.credited;
+        assets.push((asset.token, amount.amount));
+    }
+    assets[0].1 = main_amount;
+    Ok(assets)
+}
+
+// raised amount of every asset
+fn raised_assets(
+    storage: &dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+) -> StdResult<Vec<(TokenConfig, Uint128)>> {
+    split_assets(storage, project_id, project_info.current_amount, |index| {
+        ASSETS_RAISED.may_load(storage, (project_id, index))
     })
 }
 
-#[cfg(test)]
-mod tests {
-    //use super::*;
-    //use cosmwasm_std::testing::{mock_dependencies_with_balance, mock_env, mock_info};
-    //use cosmwasm_std::{coins, from_binary};
-
-    //#[test]
-    //fn proper_initialization() {
-    //let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
-
-    //let msg = InstantiateMsg { count: 17 };
-    //let info = mock_info("creator", &coins(1000, "earth"));
-
-    //// we can just call .unwrap() to assert this was a success
-    //let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-    //assert_eq!(0, res.messages.len());
-
-    //// it worked, let's query the state
-    //let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-    //let value: CountResponse = from_binary(&res).unwrap();
-    //assert_eq!(17, value.count);
-    //}
-
-    //#[test]
-    //fn increment() {
-    //let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
-
-    //let msg = InstantiateMsg { count: 17 };
-    //let info = mock_info("creator", &coins(2, "token"));
-    //let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    //// beneficiary can release it
-    //let info = mock_info("anyone", &coins(2, "token"));
-    //let msg = ExecuteMsg::Increment {};
-    //let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    //// should increase counter by 1
-    //let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-    //let value: CountResponse = from_binary(&res).unwrap();
-    //assert_eq!(18, value.count);
-    //}
-
-    //#[test]
-    //fn reset() {
-    //let mut deps = mock_dependencies_with_balance(&coins(2, "token"));
-
-    //let msg = InstantiateMsg { count: 17 };
-    //let info = mock_info("creator", &coins(2, "token"));
-    //let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-
-    //// beneficiary can release it
-    //let unauth_info = mock_info("anyone", &coins(2, "token"));
-    //let msg = ExecuteMsg::Reset { count: 5 };
-    //let res = execute(deps.as_mut(), mock_env(), unauth_info, msg);
-    //match res {
-    //Err(ContractError::Unauthorized {}) => {}
-    //_ => panic!("Must return unauthorized error"),
-    //}
-
-    //// only the original creator can reset the counter
-    //let auth_info = mock_info("creator", &coins(2, "token"));
-    //let msg = ExecuteMsg::Reset { count: 5 };
-    //let _res = execute(deps.as_mut(), mock_env(), auth_info, msg).unwrap();
-
-    //// should now be 5
-    //let res = query(deps.as_ref(), mock_env(), QueryMsg::GetCount {}).unwrap();
-    //let value: CountResponse = from_binary(&res).unwrap();
-    //assert_eq!(5, value.count);
-    //}
+// contributed amount of every asset
+fn contributed_assets(
+    storage: &dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+    contributed_amount: Uint128,
+) -> StdResult<Vec<(TokenConfig, Uint128)>> {
+    split_assets(storage, project_id, contributed_amount, |index| {
+        ASSET_CONTRIBUTIONS.may_load(storage, (project_id, contributor, index))
+    })
+}
+
+// pays out `amount` of the raised funds after `paid` was paid out before,
+// every asset in proportion to how much of it was raised
+fn payout_msgs(
+    raised: &[(TokenConfig, Uint128)],
+    current_amount: Uint128,
+    paid: Uint128,
+    amount: Uint128,
+    recipient: &Addr,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    for (token_config, raised_amount) in raised {
+        // paid shares are rounded down cumulatively so the last payout leaves no dust
+        let share = raised_amount.multiply_ratio(paid + amount, current_amount)
+            - raised_amount.multiply_ratio(paid, current_amount);
+        if !share.is_zero() {
+            msgs.push(transfer_msg(token_config.clone(), recipient, share)?);
+        }
+    }
+    Ok(msgs)
+}
+
+// payout to project owner split with platform treasury and referrers
+struct OwnerPayout {
+    // transfers of the owner share, it comes first among the paid out funds
+    owner_msgs: Vec<CosmosMsg>,
+    owner_amount: Uint128,
+    // transfers to treasury and referrers
+    msgs: Vec<CosmosMsg>,
+    fee: Uint128,
+    referral_rewards: Uint128,
+}
+
+// pays out `amount` of the raised funds to project owner. platform fee goes to treasury,
+// referrers get their reward out of what is left. withdrawn amount must not include this payout yet
+fn owner_payout(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+    amount: Uint128,
+) -> StdResult<OwnerPayout> {
+    let mut shares = vec![];
+
+    let platform_fee = CONFIG.load(storage)?.platform_fee;
+    let fee = match platform_fee {
+        Some(platform_fee) => {
+            let fee = amount * platform_fee.fee;
+            shares.push((platform_fee.treasury, fee));
+            fee
+        }
+        None => Uint128::zero(),
+    };
+
+    // every referrer gets a cut of the payout share their backers make up
+    let mut referral_rewards = Uint128::zero();
+    if project_info.referral_reward > 0 {
+        let project_referrals: Vec<Referral> = referrals()
+            .prefix(project_id)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(_, referral)| referral))
+            .collect::<StdResult<_>>()?;
+        for mut referral in project_referrals {
+            let reward = (amount - fee)
+                .multiply_ratio(referral.amount, project_info.current_amount)
+                .multiply_ratio(project_info.referral_reward, 100u64);
+            if reward.is_zero() {
+                continue;
+            }
+            referral.rewarded += reward;
+            referrals().save(storage, (project_id, &referral.referrer), &referral)?;
+            referral_rewards += reward;
+            shares.push((referral.referrer, reward));
+        }
+    }
+
+    let raised = raised_assets(storage, project_id, project_info)?;
+    let owner_amount = amount - fee - referral_rewards;
+    let owner_msgs = owner_share_msgs(
+        storage,
+        project_id,
+        project_info,
+        &raised,
+        project_info.withdrawn_amount,
+        owner_amount,
+    )?;
+
+    let mut paid = project_info.withdrawn_amount + owner_amount;
+    let mut msgs = vec![];
+    for (recipient, share) in shares {
+        msgs.extend(payout_msgs(
+            &raised,
+            project_info.current_amount,
+            paid,
+            share,
+            &recipient,
+        )?);
+        paid += share;
+    }
+
+    Ok(OwnerPayout {
+        owner_msgs,
+        owner_amount,
+        msgs,
+        fee,
+        referral_rewards,
+    })
+}
+
+// pays `amount` of the owner share after `paid` was paid out before, split among beneficiaries
+fn owner_share_msgs(
+    storage: &dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+    raised: &[(TokenConfig, Uint128)],
+    paid: Uint128,
+    amount: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    let mut paid = paid;
+    for (recipient, share) in owner_shares(storage, project_id, project_info, amount)? {
+        msgs.extend(payout_msgs(
+            raised,
+            project_info.current_amount,
+            paid,
+            share,
+            &recipient,
+        )?);
+        paid += share;
+    }
+    Ok(msgs)
+}
+
+// splits `amount` of the owner share among beneficiaries, project owner when there are none.
+// last beneficiary takes the rounding remainder
+fn owner_shares(
+    storage: &dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+    amount: Uint128,
+) -> StdResult<Vec<(Addr, Uint128)>> {
+    let beneficiaries = BENEFICIARIES
+        .may_load(storage, project_id)?
+        .unwrap_or_default();
+    if beneficiaries.is_empty() {
+        return Ok(vec![(project_info.project_owner.clone(), amount)]);
+    }
+
+    let count = beneficiaries.len();
+    let mut shares = vec![];
+    let mut left = amount;
+    for (index, beneficiary) in beneficiaries.into_iter().enumerate() {
+        let share = if index + 1 == count {
+            left
+        } else {
+            amount.multiply_ratio(beneficiary.percentage, 100u64)
+        };
+        shares.push((beneficiary.address, share));
+        left -= share;
+    }
+    Ok(shares)
+}
+
+// project stops taking contributions at end_time or once the hard cap is reached
+fn is_closed(project_info: &ProjectInfo, now: u64) -> bool {
+    project_info.end_time <= now
+        || project_info
+            .max_amount
+            .map_or(false, |max_amount| max_amount <= project_info.current_amount)
+}
+
+// records the receipt and builds cw721 mint of it to the contributor
+fn mint_receipt_msg(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    receipt_nft: Addr,
+    receipt: Receipt,
+) -> Result<CosmosMsg, ContractError> {
+    let number = RECEIPT_COUNT
+        .may_load(storage, project_id)?
+        .unwrap_or_default()
+        + 1;
+    RECEIPT_COUNT.save(storage, project_id, &number)?;
+
+    let token_id = format!("{}-{}", project_id, number);
+    RECEIPTS.save(storage, (project_id, &token_id), &receipt)?;
+
+    let mint = Cw721ExecuteMsg::Mint(MintMsg {
+        token_id,
+        owner: receipt.backer.into(),
+        token_uri: None,
+        extension: ReceiptMetadata {
+            project_id,
+            amount: receipt.amount,
+            tier: receipt.tier,
+        },
+    });
+    Ok(WasmMsg::Execute {
+        contract_addr: receipt_nft.into(),
+        msg: to_binary(&mint)?,
+        funds: vec![],
+    }
+    .into())
+}
+
+fn receipt_holder(
+    querier: &QuerierWrapper,
+    receipt_nft: &Addr,
+    token_id: &str,
+) -> StdResult<Addr> {
+    let res: OwnerOfResponse = querier.query_wasm_smart(
+        receipt_nft,
+        &Cw721QueryMsg::OwnerOf {
+            token_id: token_id.into(),
+            include_expired: None,
+        },
+    )?;
+    Ok(Addr::unchecked(res.owner))
+}
+
+pub fn try_deposit_matching_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    ratio: Decimal,
+) -> Result<Response, ContractError> {
+    let token_config = TOKEN_CONFIGS
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::ProjectNotFound { project_id })?;
+    let denom = match token_config {
+        TokenConfig::Native { denom } => denom,
+        TokenConfig::CW20 { addr: _ } => return Err(ContractError::Cw20Only {}),
+    };
+
+    // matching funds are held in main token only
+    let deposit = single_coin(&info.funds)?
+        .filter(|coin| coin.denom == denom)
+        .ok_or_else(|| ContractError::WrongDenom {
+            expected: denom.clone(),
+        })?;
+
+    let deposit = (TokenConfig::Native { denom }, deposit.amount);
+    deposit_matching_funds(deps.storage, &env, project_id, info.sender, deposit, ratio)
+}
+
+// the only coin sent, none when nothing is sent
+fn single_coin(funds: &[Coin]) -> Result<Option<&Coin>, ContractError> {
+    match funds {
+        [] => Ok(None),
+        [coin] => Ok(Some(coin)),
+        _ => Err(ContractError::MultipleDenoms {}),
+    }
+}
+
+// common part of native and cw20 deposit of matching funds
+fn deposit_matching_funds(
+    storage: &mut dyn Storage,
+    env: &Env,
+    project_id: u64,
+    sponsor: Addr,
+    (token_config, amount): (TokenConfig, Uint128),
+    ratio: Decimal,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(storage, project_id)?;
+    if project_info.status == Status::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+    if is_closed(&project_info, env.block.time.seconds()) {
+        return Err(ContractError::ProjectEnded {});
+    }
+
+    let expected = TOKEN_CONFIGS.load(storage, project_id)?;
+    if token_config != expected {
+        return Err(match expected {
+            TokenConfig::Native { denom: _ } => ContractError::NativeOnly {},
+            TokenConfig::CW20 { addr } => ContractError::WrongCw20Token { expected: addr },
+        });
+    }
+    if ratio.is_zero() {
+        return Err(ContractError::InvalidMatchingRatio {});
+    }
+
+    let pool = match MATCHING_POOLS.may_load(storage, project_id)? {
+        Some(pool) if pool.sponsor != sponsor => {
+            return Err(ContractError::MatchingPoolExists {
+                sponsor: pool.sponsor,
+            })
+        }
+        Some(pool) => MatchingPool {
+            ratio,
+            cap: pool.cap + amount,
+            ..pool
+        },
+        None => MatchingPool {
+            sponsor: sponsor.clone(),
+            ratio,
+            cap: amount,
+            matched: Uint128::zero(),
+            reclaimed: Uint128::zero(),
+        },
+    };
+    MATCHING_POOLS.save(storage, project_id, &pool)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_matching_funds")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("sponsor", sponsor)
+        .add_attribute("amount", amount)
+        .add_attribute("ratio", ratio.to_string()))
+}
+
+pub fn try_reclaim_matching_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    let mut pool = MATCHING_POOLS
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::NothingToReclaim {})?;
+    if info.sender != pool.sponsor {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    if project_info.status != Status::Cancelled && !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+
+    // unused funds always go back, matched funds only as far as contributions are refundable.
+    // nothing was matched when nothing is raised
+    let mut returned = pool.cap - pool.matched;
+    if is_refundable(&project_info) && !project_info.current_amount.is_zero() {
+        let refundable_amount = project_info.current_amount - project_info.withdrawn_amount;
+        returned += pool
+            .matched
+            .multiply_ratio(refundable_amount, project_info.current_amount);
+    }
+    let amount = returned - pool.reclaimed;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToReclaim {});
+    }
+    pool.reclaimed += amount;
+    MATCHING_POOLS.save(deps.storage, project_id, &pool)?;
+
+    let token_config = TOKEN_CONFIGS.load(deps.storage, project_id)?;
+    Ok(Response::new()
+        .add_message(transfer_msg(token_config, &info.sender, amount)?)
+        .add_attribute("action", "reclaim_matching_funds")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("sponsor", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn try_unpledge(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    amount: Uint128,
+    asset: u32,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    if project_info.status == Status::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    if is_closed(&project_info, now) {
+        return Err(ContractError::ProjectEnded {});
+    }
+
+    // receipts may have changed hands, their amounts must stay intact
+    if RECEIPT_NFTS.has(deps.storage, project_id) {
+        return Err(ContractError::UnpledgeWithReceipts {});
+    }
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let key = (project_id, &info.sender);
+    let contributed_amount = CONTRIBUTIONS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoContribution {})?;
+    let contributed =
+        contributed_assets(deps.storage, project_id, &info.sender, contributed_amount)?;
+    let (token_config, available) = contributed
+        .get(asset as usize)
+        .cloned()
+        .ok_or(ContractError::AssetNotFound { index: asset })?;
+    if available < amount {
+        return Err(ContractError::InsufficientContribution { available });
+    }
+
+    // other assets give back the credited part in proportion
+    let unpledged = if asset == 0 {
+        amount
+    } else {
+        let asset_key = (project_id, &info.sender, asset);
+        let mut contributed = ASSET_CONTRIBUTIONS.load(deps.storage, asset_key)?;
+        let credited = contributed.credited.multiply_ratio(amount, contributed.amount);
+        contributed.amount -= amount;
+        contributed.credited -= credited;
+        if contributed.amount.is_zero() {
+            ASSET_CONTRIBUTIONS.remove(deps.storage, asset_key);
+        } else {
+            ASSET_CONTRIBUTIONS.save(deps.storage, asset_key, &contributed)?;
+        }
+        ASSETS_RAISED.update(deps.storage, (project_id, asset), |raised| -> StdResult<_> {
+            let raised = raised.unwrap_or_default();
+            Ok(AssetAmount {
+                amount: raised.amount - amount,
+                credited: raised.credited - credited,
+            })
+        })?;
+        credited
+    };
+
+    let remaining = contributed_amount - unpledged;
+    if remaining.is_zero() {
+        remove_contribution(deps.storage, project_id, &info.sender)?;
+        leave_reward_tier(deps.storage, project_id, &info.sender)?;
+    } else {
+        check_contribution_limits(&project_info, remaining, Uint128::zero())?;
+        if let Some(index) = BACKER_TIERS.may_load(deps.storage, key)? {
+            let contributor = Some(&info.sender);
+            select_reward_tier(deps.storage, project_id, contributor, index, remaining)?;
+        }
+        save_contribution(deps.storage, project_id, &info.sender, remaining)?;
+    }
+
+    if let Some(referrer) = REFERRERS.may_load(deps.storage, key)? {
+        update_referred(deps.storage, project_id, &referrer, |amount| amount - unpledged)?;
+    }
+
+    // sponsor match of the unpledged part goes back to the matching pool
+    let mut unmatched = Uint128::zero();
+    if let Some(matched) = MATCHED.may_load(deps.storage, key)? {
+        unmatched = matched.multiply_ratio(unpledged, contributed_amount);
+        if matched == unmatched {
+            MATCHED.remove(deps.storage, key);
+        } else {
+            MATCHED.save(deps.storage, key, &(matched - unmatched))?;
+        }
+        MATCHING_POOLS.update(deps.storage, project_id, |pool| -> StdResult<_> {
+            let mut pool = pool.ok_or_else(|| StdError::not_found("MatchingPool"))?;
+            pool.matched -= unmatched;
+            Ok(pool)
+        })?;
+    }
+
+    project_info.current_amount -= unpledged + unmatched;
+    if project_info.status == Status::Succeeded
+        && project_info.current_amount < project_info.target_amount
+    {
+        project_info.status = Status::Ongoing;
+    }
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+
+    // penalty is paid out like the raised funds, platform fee first, then split among beneficiaries
+    let penalty = amount.multiply_ratio(project_info.unpledge_penalty, 100u64);
+    let mut shares = vec![(info.sender.clone(), amount - penalty)];
+    let fee = match CONFIG.load(deps.storage)?.platform_fee {
+        Some(platform_fee) => {
+            let fee = penalty * platform_fee.fee;
+            shares.push((platform_fee.treasury, fee));
+            fee
+        }
+        None => Uint128::zero(),
+    };
+    shares.extend(owner_shares(deps.storage, project_id, &project_info, penalty - fee)?);
+    let mut msgs = vec![];
+    for (recipient, share) in shares {
+        if !share.is_zero() {
+            msgs.push(transfer_msg(token_config.clone(), &recipient, share)?);
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "unpledge")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("contributor", info.sender)
+        .add_attribute("amount", amount)
+        .add_attribute("penalty", penalty)
+        .add_attribute("fee", fee))
+}
+
+// gives the reward tier slot of a backer back
+fn leave_reward_tier(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    contributor: &Addr,
+) -> StdResult<()> {
+    let key = (project_id, contributor);
+    if let Some(index) = BACKER_TIERS.may_load(storage, key)? {
+        let mut reward_tiers = REWARD_TIERS.load(storage, project_id)?;
+        reward_tiers[index as usize].backers -= 1;
+        REWARD_TIERS.save(storage, project_id, &reward_tiers)?;
+        BACKER_TIERS.remove(storage, key);
+    }
+    Ok(())
+}
+
+// moves contributor into the picked reward tier, backers keep their tier when they don't pick one.
+// without contributor the tier is taken for a single receipt, amount is what it stands for
+fn select_reward_tier(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    contributor: Option<&Addr>,
+    index: u32,
+    contributed_total: Uint128,
+) -> Result<(), ContractError> {
+    let mut reward_tiers = REWARD_TIERS
+        .may_load(storage, project_id)?
+        .unwrap_or_default();
+    let reward_tier = reward_tiers
+        .get(index as usize)
+        .ok_or(ContractError::TierNotFound { index })?;
+    if contributed_total < reward_tier.min_amount {
+        return Err(ContractError::BelowTierMinimum {
+            index,
+            min_amount: reward_tier.min_amount,
+        });
+    }
+
+    let current = match contributor {
+        Some(contributor) => BACKER_TIERS.may_load(storage, (project_id, contributor))?,
+        None => None,
+    };
+    if current == Some(index) {
+        return Ok(());
+    }
+    if let Some(max_backers) = reward_tier.max_backers {
+        if max_backers <= reward_tier.backers {
+            return Err(ContractError::TierSoldOut { index });
+        }
+    }
+
+    // switching tier gives the old slot back
+    if let Some(current) = current {
+        reward_tiers[current as usize].backers -= 1;
+    }
+    reward_tiers[index as usize].backers += 1;
+    REWARD_TIERS.save(storage, project_id, &reward_tiers)?;
+    if let Some(contributor) = contributor {
+        BACKER_TIERS.save(storage, (project_id, contributor), &index)?;
+    }
+
+    Ok(())
+}
+
+pub fn try_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    addr: String,
+    expires: Option<u64>,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    let expires = expires.unwrap_or(now + OWNERSHIP_PROPOSAL_EXPIRY);
+    if expires <= now {
+        return Err(ContractError::InvalidExpiry {});
+    }
+
+    let pending_owner = PendingOwner {
+        owner: deps.api.addr_validate(&addr)?,
+        expires,
+    };
+    PENDING_OWNERS.save(deps.storage, project_id, &pending_owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_owner")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("new_owner", pending_owner.owner)
+        .add_attribute("expires", expires.to_string()))
+}
+
+pub fn try_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    let pending_owner = PENDING_OWNERS
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::NoOwnershipProposal {})?;
+    if info.sender != pending_owner.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending_owner.expires <= env.block.time.seconds() {
+        return Err(ContractError::OwnershipProposalExpired {
+            expires: pending_owner.expires,
+        });
+    }
+
+    let previous_owner = project_info.project_owner;
+    project_info.project_owner = pending_owner.owner;
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+    PENDING_OWNERS.remove(deps.storage, project_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "accept_ownership")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("previous_owner", previous_owner)
+        .add_attribute("owner", project_info.project_owner))
+}
+
+pub fn try_cancel_ownership_proposal(
+    deps: DepsMut,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !PENDING_OWNERS.has(deps.storage, project_id) {
+        return Err(ContractError::NoOwnershipProposal {});
+    }
+    PENDING_OWNERS.remove(deps.storage, project_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_ownership_proposal")
+        .add_attribute("project_id", project_id.to_string()))
+}
+
+pub fn try_update_beneficiaries(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    beneficiaries: Vec<BeneficiaryMsg>,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if is_closed(&project_info, env.block.time.seconds()) {
+        return Err(ContractError::ProjectEnded {});
+    }
+
+    let beneficiaries = beneficiaries_of(deps.api, beneficiaries)?;
+    if beneficiaries.is_empty() {
+        BENEFICIARIES.remove(deps.storage, project_id);
+    } else {
+        BENEFICIARIES.save(deps.storage, project_id, &beneficiaries)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_beneficiaries")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("count", beneficiaries.len().to_string()))
+}
+
+pub fn try_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    if !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+
+    if project_info.status == Status::Withdrawn {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+
+    if project_info.status != Status::Succeeded {
+        return Err(ContractError::NotSucceeded {});
+    }
+
+    if MILESTONES.has(deps.storage, project_id) {
+        return Err(ContractError::MilestonesConfigured {});
+    }
+    if GOVERNANCES.has(deps.storage, project_id) {
+        return Err(ContractError::GovernanceConfigured {});
+    }
+    check_no_pending_round(deps.storage, project_id)?;
+
+    // pay out everything not withdrawn yet, exactly once
+    let withdraw_amount = project_info.current_amount - project_info.withdrawn_amount;
+    let payout = owner_payout(deps.storage, project_id, &project_info, withdraw_amount)?;
+
+    // vested owner share stays in the contract until claimed, fee and rewards go out right away
+    let msgs = match VESTINGS.may_load(deps.storage, project_id)? {
+        Some(mut vesting) => {
+            vesting.start_time = Some(now);
+            vesting.offset = project_info.withdrawn_amount;
+            vesting.amount = payout.owner_amount;
+            VESTINGS.save(deps.storage, project_id, &vesting)?;
+            payout.msgs
+        }
+        None => [payout.owner_msgs, payout.msgs].concat(),
+    };
+
+    project_info.withdrawn_amount += withdraw_amount;
+    project_info.fee_amount += payout.fee;
+    project_info.status = Status::Withdrawn;
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "withdraw")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("recipient", project_info.project_owner)
+        .add_attribute("amount", withdraw_amount)
+        .add_attribute("fee", payout.fee)
+        .add_attribute("referral_rewards", payout.referral_rewards))
+}
+
+pub fn try_claim_vested(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut vesting = VESTINGS
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::NothingToClaim {})?;
+    let claimable = vested_amount(&vesting, env.block.time.seconds()) - vesting.claimed;
+    if claimable.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    // owner share is paid out slice by slice, every asset in proportion
+    let raised = raised_assets(deps.storage, project_id, &project_info)?;
+    let msgs = owner_share_msgs(
+        deps.storage,
+        project_id,
+        &project_info,
+        &raised,
+        vesting.offset + vesting.claimed,
+        claimable,
+    )?;
+    vesting.claimed += claimable;
+    VESTINGS.save(deps.storage, project_id, &vesting)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "claim_vested")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("recipient", project_info.project_owner)
+        .add_attribute("amount", claimable)
+        .add_attribute("remaining", vesting.amount - vesting.claimed))
+}
+
+// owner share unlocked by now, nothing before the cliff then linear until the end of duration
+fn vested_amount(vesting: &Vesting, now: u64) -> Uint128 {
+    let start_time = match vesting.start_time {
+        Some(start_time) => start_time,
+        None => return Uint128::zero(),
+    };
+    let elapsed = now.saturating_sub(start_time);
+    if elapsed < vesting.cliff {
+        Uint128::zero()
+    } else if vesting.duration <= elapsed {
+        vesting.amount
+    } else {
+        vesting.amount.multiply_ratio(elapsed, vesting.duration)
+    }
+}
+
+pub fn try_release_milestone(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    index: u32,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    if !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+
+    if project_info.status != Status::Succeeded {
+        return Err(ContractError::NotSucceeded {});
+    }
+    check_no_pending_round(deps.storage, project_id)?;
+
+    let mut milestones = MILESTONES
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default();
+    let milestone = milestones
+        .get_mut(index as usize)
+        .ok_or(ContractError::MilestoneNotFound { index })?;
+    if milestone.released {
+        return Err(ContractError::MilestoneAlreadyReleased { index });
+    }
+    if now < milestone.unlock_time {
+        return Err(ContractError::MilestoneLocked {
+            unlock_time: milestone.unlock_time,
+        });
+    }
+    milestone.released = true;
+
+    // last milestone takes whatever is left so rounding never leaves dust behind
+    let release_amount = if milestones.iter().all(|m| m.released) {
+        project_info.status = Status::Withdrawn;
+        project_info.current_amount - project_info.withdrawn_amount
+    } else {
+        project_info
+            .current_amount
+            .multiply_ratio(milestones[index as usize].percentage, 100u64)
+    };
+    let payout = owner_payout(deps.storage, project_id, &project_info, release_amount)?;
+    project_info.withdrawn_amount += release_amount;
+    project_info.fee_amount += payout.fee;
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+    MILESTONES.save(deps.storage, project_id, &milestones)?;
+
+    Ok(Response::new()
+        .add_messages(payout.owner_msgs)
+        .add_messages(payout.msgs)
+        .add_attribute("action", "release_milestone")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("index", index.to_string())
+        .add_attribute("recipient", project_info.project_owner)
+        .add_attribute("amount", release_amount)
+        .add_attribute("fee", payout.fee)
+        .add_attribute("referral_rewards", payout.referral_rewards))
+}
+
+pub fn try_create_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    amount: Uint128,
+    purpose: String,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender != project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let governance = GOVERNANCES
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::NoGovernance {})?;
+
+    let now: u64 = env.block.time.seconds();
+    if !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+    if project_info.status == Status::Withdrawn {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+    if project_info.status != Status::Succeeded {
+        return Err(ContractError::NotSucceeded {});
+    }
+
+    // open proposals keep their amount reserved
+    let mut available = project_info.current_amount - project_info.withdrawn_amount;
+    for item in PROPOSALS
+        .prefix(project_id)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (_, proposal) = item?;
+        if proposal.status == ProposalStatus::Open {
+            available -= proposal.amount;
+        }
+    }
+    if amount.is_zero() || available < amount {
+        return Err(ContractError::InsufficientFunds { available });
+    }
+
+    let proposal_id = PROPOSAL_COUNT
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default()
+        + 1;
+    PROPOSAL_COUNT.save(deps.storage, project_id, &proposal_id)?;
+    let proposal = Proposal {
+        amount,
+        purpose,
+        end_time: now + governance.voting_period,
+        yes: Uint128::zero(),
+        no: Uint128::zero(),
+        status: ProposalStatus::Open,
+    };
+    PROPOSALS.save(deps.storage, (project_id, proposal_id), &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_proposal")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("amount", amount))
+}
+
+fn load_proposal(
+    storage: &dyn Storage,
+    project_id: u64,
+    proposal_id: u64,
+) -> Result<Proposal, ContractError> {
+    PROPOSALS
+        .may_load(storage, (project_id, proposal_id))?
+        .ok_or(ContractError::ProposalNotFound { proposal_id })
+}
+
+pub fn try_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    (project_id, proposal_id): (u64, u64),
+    approve: bool,
+    receipts: Vec<String>,
+) -> Result<Response, ContractError> {
+    let project_info = load_project(deps.storage, project_id)?;
+    if info.sender == project_info.project_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut proposal = load_proposal(deps.storage, project_id, proposal_id)?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen {});
+    }
+    if proposal.end_time <= env.block.time.seconds() {
+        return Err(ContractError::VotingEnded {});
+    }
+
+    // receipts may have changed hands, their holders vote and every receipt votes once
+    let weight = match RECEIPT_NFTS.may_load(deps.storage, project_id)? {
+        Some(receipt_nft) => {
+            if receipts.is_empty() {
+                return Err(ContractError::ReceiptRequired {});
+            }
+            let mut weight = Uint128::zero();
+            for token_id in receipts {
+                let receipt = RECEIPTS
+                    .may_load(deps.storage, (project_id, &token_id))?
+                    .ok_or_else(|| ContractError::ReceiptNotFound {
+                        token_id: token_id.clone(),
+                    })?;
+                if receipt_holder(&deps.querier, &receipt_nft, &token_id)? != info.sender {
+                    return Err(ContractError::Unauthorized {});
+                }
+                let key = (project_id, proposal_id, token_id.as_str());
+                if RECEIPT_VOTES.has(deps.storage, key) {
+                    return Err(ContractError::AlreadyVoted {});
+                }
+                RECEIPT_VOTES.save(deps.storage, key, &approve)?;
+                weight += receipt.amount;
+            }
+            weight
+        }
+        None => {
+            let key = (project_id, proposal_id, &info.sender);
+            if VOTES.has(deps.storage, key) {
+                return Err(ContractError::AlreadyVoted {});
+            }
+            let weight = CONTRIBUTIONS
+                .may_load(deps.storage, (project_id, &info.sender))?
+                .ok_or(ContractError::NoContribution {})?;
+            VOTES.save(deps.storage, key, &approve)?;
+            weight
+        }
+    };
+
+    if approve {
+        proposal.yes += weight;
+    } else {
+        proposal.no += weight;
+    }
+    PROPOSALS.save(deps.storage, (project_id, proposal_id), &proposal)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("voter", info.sender)
+        .add_attribute("approve", approve.to_string())
+        .add_attribute("weight", weight))
+}
+
+pub fn try_execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    project_id: u64,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    let governance = GOVERNANCES
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::NoGovernance {})?;
+    let mut proposal = load_proposal(deps.storage, project_id, proposal_id)?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(ContractError::ProposalNotOpen {});
+    }
+    if env.block.time.seconds() < proposal.end_time {
+        return Err(ContractError::VotingNotEnded {
+            end_time: proposal.end_time,
+        });
+    }
+
+    // abandoned projects refund whatever was not released yet
+    let turnout = proposal.yes + proposal.no;
+    let voting_weight = voting_weight(deps.storage, project_id, &project_info)?;
+    let passed = project_info.status == Status::Succeeded
+        && voting_weight * governance.quorum <= turnout
+        && !proposal.yes.is_zero()
+        && turnout * governance.threshold <= proposal.yes;
+    if passed {
+        check_no_pending_round(deps.storage, project_id)?;
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "execute_proposal")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("proposal_id", proposal_id.to_string());
+    if passed {
+        let payout = owner_payout(deps.storage, project_id, &project_info, proposal.amount)?;
+        project_info.withdrawn_amount += proposal.amount;
+        project_info.fee_amount += payout.fee;
+        if project_info.withdrawn_amount == project_info.current_amount {
+            project_info.status = Status::Withdrawn;
+        }
+        PROJECTS.save(deps.storage, project_id, &project_info)?;
+        proposal.status = ProposalStatus::Executed;
+        res = res
+            .add_messages(payout.owner_msgs)
+            .add_messages(payout.msgs)
+            .add_attribute("recipient", project_info.project_owner)
+            .add_attribute("amount", proposal.amount)
+            .add_attribute("fee", payout.fee)
+            .add_attribute("referral_rewards", payout.referral_rewards);
+    } else {
+        proposal.status = ProposalStatus::Rejected;
+    }
+    PROPOSALS.save(deps.storage, (project_id, proposal_id), &proposal)?;
+
+    Ok(res.add_attribute("passed", passed.to_string()))
+}
+
+// contribution weight backers can vote with. matched funds, cw20 contributions older versions
+// credited to the token address and a contribution the owner made before taking the project over
+// have no voter
+fn voting_weight(
+    storage: &dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+) -> StdResult<Uint128> {
+    let mut weight = project_info.current_amount;
+    if let Some(pool) = MATCHING_POOLS.may_load(storage, project_id)? {
+        weight -= pool.matched;
+    }
+    weight -= ROUND_MATCHED.may_load(storage, project_id)?.unwrap_or_default();
+    let mut excluded = vec![];
+    if let TokenConfig::CW20 { addr } = TOKEN_CONFIGS.load(storage, project_id)? {
+        excluded.push(addr);
+    }
+    if !RECEIPT_NFTS.has(storage, project_id) {
+        excluded.push(project_info.project_owner.clone());
+    }
+    for addr in excluded {
+        weight -= CONTRIBUTIONS
+            .may_load(storage, (project_id, &addr))?
+            .unwrap_or_default();
+    }
+    Ok(weight)
+}
+
+pub fn try_abandon(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != project_info.project_owner && info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now: u64 = env.block.time.seconds();
+    if !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+
+    if project_info.status == Status::Withdrawn {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+
+    if project_info.status != Status::Succeeded {
+        return Err(ContractError::NotSucceeded {});
+    }
+
+    project_info.status = Status::Abandoned;
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+    let msgs = round_matched_refund(deps.storage, project_id, &project_info)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "abandon")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute(
+            "refundable_amount",
+            project_info.current_amount - project_info.withdrawn_amount,
+        ))
+}
+
+pub fn try_cancel(
+    deps: DepsMut,
+    info: MessageInfo,
+    project_id: u64,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != project_info.project_owner && info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if project_info.status == Status::Cancelled {
+        return Err(ContractError::ProjectCancelled {});
+    }
+
+    // released milestones count as withdrawal too
+    if project_info.status == Status::Withdrawn || !project_info.withdrawn_amount.is_zero() {
+        return Err(ContractError::AlreadyWithdrawn {});
+    }
+
+    project_info.status = Status::Cancelled;
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+    let msgs = round_matched_refund(deps.storage, project_id, &project_info)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "cancel")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("refundable_amount", project_info.current_amount))
+}
+
+// backers are refunded their own contributions only, so the round matches of a project given
+// up go back to admin as far as they were not paid out yet
+fn round_matched_refund(
+    storage: &mut dyn Storage,
+    project_id: u64,
+    project_info: &ProjectInfo,
+) -> StdResult<Vec<CosmosMsg>> {
+    let matched = match ROUND_MATCHED.may_load(storage, project_id)? {
+        Some(matched) => matched,
+        None => return Ok(vec![]),
+    };
+    ROUND_MATCHED.remove(storage, project_id);
+    let unreleased = project_info.current_amount - project_info.withdrawn_amount;
+    let amount = matched.multiply_ratio(unreleased, project_info.current_amount);
+    if amount.is_zero() {
+        return Ok(vec![]);
+    }
+    let admin = CONFIG.load(storage)?.admin;
+    let token_config = TOKEN_CONFIGS.load(storage, project_id)?;
+    Ok(vec![transfer_msg(token_config, &admin, amount)?])
+}
+
+pub fn try_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    project_id: u64,
+    receipt: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut project_info = load_project(deps.storage, project_id)?;
+
+    // cancelled projects refund right away, whatever was raised
+    let now: u64 = env.block.time.seconds();
+    if project_info.status != Status::Cancelled && !is_closed(&project_info, now) {
+        return Err(ContractError::ProjectNotEnded {});
+    }
+
+    if !is_refundable(&project_info) {
+        return Err(ContractError::NotFailed {});
+    }
+
+    let key = (project_id, &info.sender);
+    let (contributed_amount, contributed) = match RECEIPT_NFTS.may_load(deps.storage, project_id)? {
+        Some(receipt_nft) => {
+            let token_id = receipt.ok_or(ContractError::ReceiptRequired {})?;
+            let amount = claim_receipt(
+                deps.storage,
+                &deps.querier,
+                project_id,
+                &receipt_nft,
+                &token_id,
+                &info.sender,
+            )?;
+            // receipt projects take main token only
+            let token_config = TOKEN_CONFIGS.load(deps.storage, project_id)?;
+            (amount, vec![(token_config, amount)])
+        }
+        None => {
+            if REFUNDS.has(deps.storage, key) {
+                return Err(ContractError::AlreadyRefunded {});
+            }
+            let contributed_amount = CONTRIBUTIONS
+                .may_load(deps.storage, key)?
+                .ok_or(ContractError::NoContribution {})?;
+            let contributed =
+                contributed_assets(deps.storage, project_id, &info.sender, contributed_amount)?;
+            // clear the contribution so it is paid out only once
+            remove_contribution(deps.storage, project_id, &info.sender)?;
+            for index in 1..contributed.len() as u32 {
+                ASSET_CONTRIBUTIONS.remove(deps.storage, (project_id, &info.sender, index));
+            }
+            (contributed_amount, contributed)
+        }
+    };
+
+    // everything comes back unless milestones were released before the project got abandoned,
+    // every asset in the same token it was contributed
+    let refundable_amount = project_info.current_amount - project_info.withdrawn_amount;
+    let refund_amount =
+        contributed_amount.multiply_ratio(refundable_amount, project_info.current_amount);
+    let mut msgs = vec![];
+    for (token_config, amount) in contributed {
+        let amount = amount.multiply_ratio(refundable_amount, project_info.current_amount);
+        if !amount.is_zero() {
+            msgs.push(transfer_msg(token_config, &info.sender, amount)?);
+        }
+    }
+
+    REFUNDS.update(deps.storage, key, |refunded| -> StdResult<_> {
+        Ok(refunded.unwrap_or_default() + refund_amount)
+    })?;
+
+    project_info.refunded_amount += refund_amount;
+    if project_info.status != Status::Abandoned && project_info.status != Status::Cancelled {
+        project_info.status = Status::Refunding;
+    }
+    PROJECTS.save(deps.storage, project_id, &project_info)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "refund")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("recipient", info.sender)
+        .add_attribute("amount", refund_amount))
+}
+
+// closed project gives contributions back when it failed, got abandoned or cancelled
+fn is_refundable(project_info: &ProjectInfo) -> bool {
+    match project_info.status {
+        Status::Abandoned | Status::Refunding | Status::Cancelled => true,
+        _ => project_info.current_amount < project_info.target_amount,
+    }
+}
+
+// marks receipt refunded when sender holds it, returns contributed amount it stands for
+fn claim_receipt(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    project_id: u64,
+    receipt_nft: &Addr,
+    token_id: &str,
+    sender: &Addr,
+) -> Result<Uint128, ContractError> {
+    let mut receipt = RECEIPTS
+        .may_load(storage, (project_id, token_id))?
+        .ok_or_else(|| ContractError::ReceiptNotFound {
+            token_id: token_id.into(),
+        })?;
+    if receipt.refunded {
+        return Err(ContractError::AlreadyRefunded {});
+    }
+    if receipt_holder(querier, receipt_nft, token_id)? != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    receipt.refunded = true;
+    RECEIPTS.save(storage, (project_id, token_id), &receipt)?;
+
+    // contribution of the original backer no longer holds this amount
+    let key = (project_id, &receipt.backer);
+    let remaining = CONTRIBUTIONS.may_load(storage, key)?.unwrap_or_default() - receipt.amount;
+    save_contribution(storage, project_id, &receipt.backer, remaining)?;
+
+    Ok(receipt.amount)
+}
+
+pub fn try_reassign_legacy_contribution(
+    deps: DepsMut,
+    info: MessageInfo,
+    project_id: u64,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // older versions credited cw20 contributions to the cw20 token address itself
+    let token_config = TOKEN_CONFIGS
+        .may_load(deps.storage, project_id)?
+        .ok_or(ContractError::ProjectNotFound { project_id })?;
+    let legacy_addr = match token_config {
+        TokenConfig::Native { denom: _ } => return Err(ContractError::Cw20Only {}),
+        TokenConfig::CW20 { addr } => addr,
+    };
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if recipient == legacy_addr {
+        return Err(ContractError::InvalidReassignRecipient { recipient });
+    }
+    if REFUNDS.has(deps.storage, (project_id, &recipient)) {
+        return Err(ContractError::AlreadyRefunded {});
+    }
+
+    let legacy_amount = CONTRIBUTIONS
+        .may_load(deps.storage, (project_id, &legacy_addr))?
+        .unwrap_or_default();
+    if amount.is_zero() || legacy_amount < amount {
+        return Err(ContractError::InsufficientLegacyContribution {
+            available: legacy_amount,
+        });
+    }
+
+    // legacy entry is not counted as a contributor
+    let remaining = legacy_amount - amount;
+    if remaining.is_zero() {
+        CONTRIBUTIONS.remove(deps.storage, (project_id, &legacy_addr));
+    } else {
+        CONTRIBUTIONS.save(deps.storage, (project_id, &legacy_addr), &remaining)?;
+    }
+    let recipient_amount = CONTRIBUTIONS
+        .may_load(deps.storage, (project_id, &recipient))?
+        .unwrap_or_default()
+        + amount;
+    save_contribution(deps.storage, project_id, &recipient, recipient_amount)?;
+
+    // in receipt projects only receipts can be refunded, so the balance gets one too
+    let mut msgs = vec![];
+    if let Some(receipt_nft) = RECEIPT_NFTS.may_load(deps.storage, project_id)? {
+        let receipt = Receipt {
+            backer: recipient.clone(),
+            amount,
+            tier: None,
+            refunded: false,
+        };
+        msgs.push(mint_receipt_msg(deps.storage, project_id, receipt_nft, receipt)?);
+    }
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "reassign_legacy_contribution")
+        .add_attribute("project_id", project_id.to_string())
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("remaining", remaining))
+}
+
+pub fn try_create_round(
+    deps: DepsMut,
+    info: MessageInfo,
+    token: Token,
+    mut project_ids: Vec<u64>,
+    attestor: Option<String>,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let token = token_config_of(token);
+    project_ids.sort_unstable();
+    project_ids.dedup();
+    for &project_id in &project_ids {
+        let token_config = TOKEN_CONFIGS
+            .may_load(deps.storage, project_id)?
+            .ok_or(ContractError::ProjectNotFound { project_id })?;
+        if token_config != token {
+            return Err(ContractError::RoundTokenMismatch { project_id });
+        }
+        // a match could no longer be paid out, or would skew payouts already made
+        let project_info = load_project(deps.storage, project_id)?;
+        if project_info.status == Status::Withdrawn || !project_info.withdrawn_amount.is_zero() {
+            return Err(ContractError::RoundProjectWithdrawn { project_id });
+        }
+        // projects wait for the round to pay out, so it can't outlast them
+        if end_time < project_info.end_time {
+            return Err(ContractError::RoundProjectEndsLate { project_id });
+        }
+    }
+
+    let round = Round {
+        token,
+        project_ids,
+        attestor: attestor.map(|a| deps.api.addr_validate(&a)).transpose()?,
+        end_time,
+        matching_pool: Uint128::zero(),
+        status: RoundStatus::Open,
+        matches: vec![],
+    };
+    let round_id = ROUND_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    ROUND_COUNT.save(deps.storage, &round_id)?;
+    for &project_id in &round.project_ids {
+        ROUND_SQRT_SUMS.save(deps.storage, (project_id, round_id), &Uint128::zero())?;
+    }
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_round")
+        .add_attribute("round_id", round_id.to_string()))
+}
+
+fn load_round(storage: &dyn Storage, round_id: u64) -> Result<Round, ContractError> {
+    ROUNDS
+        .may_load(storage, round_id)?
+        .ok_or(ContractError::RoundNotFound { round_id })
+}
+
+pub fn try_fund_round(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let round = load_round(deps.storage, round_id)?;
+    let denom = match round.token {
+        TokenConfig::Native { denom } => denom,
+        TokenConfig::CW20 { addr: _ } => return Err(ContractError::Cw20Only {}),
+    };
+    let funds = single_coin(&info.funds)?
+        .filter(|coin| coin.denom == denom)
+        .ok_or_else(|| ContractError::WrongDenom {
+            expected: denom.clone(),
+        })?;
+
+    let funds = (TokenConfig::Native { denom }, funds.amount);
+    fund_round(deps.storage, round_id, info.sender, funds)
+}
+
+// common part of native and cw20 funding of a round
+fn fund_round(
+    storage: &mut dyn Storage,
+    round_id: u64,
+    funder: Addr,
+    (token_config, amount): (TokenConfig, Uint128),
+) -> Result<Response, ContractError> {
+    let mut round = load_round(storage, round_id)?;
+    if round.status != RoundStatus::Open {
+        return Err(ContractError::RoundNotOpen {});
+    }
+    if token_config != round.token {
+        return Err(match round.token {
+            TokenConfig::Native { denom: _ } => ContractError::NativeOnly {},
+            TokenConfig::CW20 { addr } => ContractError::WrongCw20Token { expected: addr },
+        });
+    }
+
+    round.matching_pool += amount;
+    ROUNDS.save(storage, round_id, &round)?;
+    ROUND_FUNDS.update(storage, (round_id, &funder), |funds| -> StdResult<_> {
+        Ok(funds.unwrap_or_default() + amount)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("funder", funder)
+        .add_attribute("amount", amount))
+}
+
+pub fn try_attest_contributors(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let round = load_round(deps.storage, round_id)?;
+    if round.attestor.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if round.status != RoundStatus::Open {
+        return Err(ContractError::RoundNotOpen {});
+    }
+
+    // contributions made before the attestation count from now on,
+    // cw20 contributions older versions credited to the token address never do
+    for address in &addresses {
+        let address = deps.api.addr_validate(address)?;
+        if ATTESTATIONS.has(deps.storage, (round_id, &address)) {
+            continue;
+        }
+        ATTESTATIONS.save(deps.storage, (round_id, &address), &true)?;
+        if round.token == (TokenConfig::CW20 { addr: address.clone() }) {
+            continue;
+        }
+        for &project_id in &round.project_ids {
+            let contributed = match CONTRIBUTIONS.may_load(deps.storage, (project_id, &address))? {
+                Some(contributed) => Uint128::new(isqrt(contributed.u128())),
+                None => continue,
+            };
+            let key = (project_id, round_id);
+            ROUND_SQRT_SUMS.update(deps.storage, key, |sum| -> StdResult<_> {
+                Ok(sum.unwrap_or_default() + contributed)
+            })?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "attest_contributors")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("count", addresses.len().to_string()))
+}
+
+pub fn try_tally_round(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut round = load_round(deps.storage, round_id)?;
+    if round.status != RoundStatus::Open {
+        return Err(ContractError::RoundNotOpen {});
+    }
+
+    // contributions must be final before matches are fixed, projects end by the round end
+    let now: u64 = env.block.time.seconds();
+    if now < round.end_time {
+        return Err(ContractError::RoundNotEnded {
+            end_time: round.end_time,
+        });
+    }
+
+    round.matches = round_matches(deps.storage, round_id, &round, now)?;
+    round.status = RoundStatus::Tallied;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "tally_round")
+        .add_attribute("round_id", round_id.to_string()))
+}
+
+pub fn try_distribute_round(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut round = load_round(deps.storage, round_id)?;
+    if round.status != RoundStatus::Tallied {
+        return Err(ContractError::RoundNotTallied {});
+    }
+
+    // matches become matched funds of the projects, so they are paid out like the rest of
+    // the raised funds. projects abandoned since the tally get nothing
+    let mut distributed = Uint128::zero();
+    for project_match in &round.matches {
+        let project_id = project_match.project_id;
+        ROUND_SQRT_SUMS.remove(deps.storage, (project_id, round_id));
+        let mut project_info = load_project(deps.storage, project_id)?;
+        if project_match.amount.is_zero() || project_info.status != Status::Succeeded {
+            continue;
+        }
+        project_info.current_amount += project_match.amount;
+        PROJECTS.save(deps.storage, project_id, &project_info)?;
+        ROUND_MATCHED.update(deps.storage, project_id, |matched| -> StdResult<_> {
+            Ok(matched.unwrap_or_default() + project_match.amount)
+        })?;
+        distributed += project_match.amount;
+    }
+    // rounding dust, or the whole pool when no project qualified
+    let mut msgs = vec![];
+    let remainder = round.matching_pool - distributed;
+    if !remainder.is_zero() {
+        msgs.push(transfer_msg(round.token.clone(), &config.admin, remainder)?);
+    }
+
+    round.status = RoundStatus::Distributed;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "distribute_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("distributed", distributed)
+        .add_attribute("remainder", remainder))
+}
+
+pub fn try_cancel_round(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut round = load_round(deps.storage, round_id)?;
+    if round.status == RoundStatus::Distributed || round.status == RoundStatus::Cancelled {
+        return Err(ContractError::RoundClosed {});
+    }
+
+    for &project_id in &round.project_ids {
+        ROUND_SQRT_SUMS.remove(deps.storage, (project_id, round_id));
+    }
+    round.status = RoundStatus::Cancelled;
+    ROUNDS.save(deps.storage, round_id, &round)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_round")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("matching_pool", round.matching_pool))
+}
+
+pub fn try_reclaim_round_funds(
+    deps: DepsMut,
+    info: MessageInfo,
+    round_id: u64,
+) -> Result<Response, ContractError> {
+    let round = load_round(deps.storage, round_id)?;
+    if round.status != RoundStatus::Cancelled {
+        return Err(ContractError::RoundNotCancelled {});
+    }
+    let amount = ROUND_FUNDS
+        .may_load(deps.storage, (round_id, &info.sender))?
+        .ok_or(ContractError::NothingToReclaim {})?;
+    ROUND_FUNDS.remove(deps.storage, (round_id, &info.sender));
+
+    Ok(Response::new()
+        .add_message(transfer_msg(round.token, &info.sender, amount)?)
+        .add_attribute("action", "reclaim_round_funds")
+        .add_attribute("round_id", round_id.to_string())
+        .add_attribute("funder", info.sender)
+        .add_attribute("amount", amount))
+}
+
+// splits the matching pool by (sum of square roots of contributions)^2 of every project.
+// projects giving contributions back get no match
+fn round_matches(
+    storage: &dyn Storage,
+    round_id: u64,
+    round: &Round,
+    now: u64,
+) -> StdResult<Vec<ProjectMatch>> {
+    let mut weights = vec![];
+    for &project_id in &round.project_ids {
+        let project_info = PROJECTS.load(storage, project_id)?;
+        let excluded = match project_info.status {
+            Status::Cancelled | Status::Abandoned => true,
+            _ => is_closed(&project_info, now) && is_refundable(&project_info),
+        };
+        let sum_sqrt = if excluded {
+            Uint128::zero()
+        } else if round.attestor.is_some() {
+            ROUND_SQRT_SUMS.load(storage, (project_id, round_id))?
+        } else {
+            SQRT_SUMS.may_load(storage, project_id)?.unwrap_or_default()
+        };
+        weights.push((project_id, sum_sqrt.checked_mul(sum_sqrt)?));
+    }
+
+    let total_weight = weights
+        .iter()
+        .try_fold(Uint128::zero(), |total, (_, weight)| total.checked_add(*weight))?;
+    Ok(weights
+        .into_iter()
+        .map(|(project_id, weight)| ProjectMatch {
+            project_id,
+            amount: if total_weight.is_zero() {
+                Uint128::zero()
+            } else {
+                round.matching_pool.multiply_ratio(weight, total_weight)
+            },
+        })
+        .collect())
+}
+
+// integer square root, rounded down
+pub(crate) fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    // start from a power of two above the root, half the bit length rounded up,
+    // so newton steps only go down
+    let mut x = 1u128 << ((129 - n.leading_zeros()) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if x <= y {
+            return x;
+        }
+        x = y;
+    }
+}
+
+// builds bank send or cw20 transfer message depending on token config
+fn transfer_msg(
+    token_config: TokenConfig,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    match token_config {
+        TokenConfig::Native { denom } => Ok(CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient.into(),
+            amount: vec![Coin { denom, amount }],
+        })),
+        TokenConfig::CW20 { addr } => Cw20Contract(addr).call(Cw20ExecuteMsg::Transfer {
+            recipient: recipient.into(),
+            amount,
+        }),
+    }
+}
+
+#[cfg_attr(not(any(feature = "library", feature = "factory")), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetProjectInfo { project_id } => {
+            to_binary(&query_project_info(deps, env, project_id)?)
+        }
+        QueryMsg::ListProjects { start_after, limit } => {
+            to_binary(&query_list_projects(deps, env, start_after, limit)?)
+        }
+        QueryMsg::GetContribution {
+            project_id,
+            address,
+        } => to_binary(&query_contribution(deps, project_id, address)?),
+        QueryMsg::ListContributions {
+            project_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_contributions(deps, project_id, start_after, limit)?),
+        QueryMsg::GetContributorCount { project_id } => {
+            to_binary(&query_contributor_count(deps, project_id)?)
+        }
+        QueryMsg::GetContributionAllowance {
+            project_id,
+            address,
+        } => to_binary(&query_contribution_allowance(deps, project_id, address)?),
+        QueryMsg::GetRewardTiers { project_id } => {
+            to_binary(&query_reward_tiers(deps, project_id)?)
+        }
+        QueryMsg::GetReferral {
+            project_id,
+            referrer,
+        } => to_binary(&query_referral(deps, project_id, referrer)?),
+        QueryMsg::ListTopReferrers { project_id, limit } => {
+            to_binary(&query_top_referrers(deps, project_id, limit)?)
+        }
+        QueryMsg::GetReceipt {
+            project_id,
+            token_id,
+        } => to_binary(&query_receipt(deps, project_id, token_id)?),
+        QueryMsg::GetProposal {
+            project_id,
+            proposal_id,
+        } => to_binary(&query_proposal(deps, project_id, proposal_id)?),
+        QueryMsg::ListProposals {
+            project_id,
+            start_after,
+            limit,
+        } => to_binary(&query_list_proposals(deps, project_id, start_after, limit)?),
+        QueryMsg::GetVesting { project_id } => {
+            to_binary(&query_vesting(deps, env, project_id)?)
+        }
+        QueryMsg::GetMatchingPool { project_id } => {
+            to_binary(&query_matching_pool(deps, project_id)?)
+        }
+        QueryMsg::GetRound { round_id } => to_binary(&query_round(deps, round_id)?),
+        QueryMsg::EstimateRoundMatches { round_id } => {
+            to_binary(&query_round_estimates(deps, env, round_id)?)
+        }
+        QueryMsg::GetLegacyContribution { project_id } => {
+            to_binary(&query_legacy_contribution(deps, project_id)?)
+        }
+    }
+}
+
+fn token_config_of(token: Token) -> TokenConfig {
+    match token {
+        Token::Native { denom } => TokenConfig::Native { denom },
+        Token::CW20 { addr } => TokenConfig::CW20 { addr },
+    }
+}
+
+fn token_of(token_config: TokenConfig) -> Token {
+    match token_config {
+        TokenConfig::Native{ denom } => {
+            Token::Native{denom}
+        },
+        TokenConfig::CW20{ addr } => {
+            Token::CW20{addr}
+        }
+    }
+}
+
+fn query_project_info(deps: Deps, env: Env, project_id: u64) -> StdResult<GetProjectInfoResponse> {
+    let project_info = PROJECTS.load(deps.storage, project_id)?;
+    project_info_response(deps, &env, project_id, project_info)
+}
+
+fn query_list_projects(
+    deps: Deps,
+    env: Env,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListProjectsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let projects = PROJECTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (project_id, project_info) = item?;
+            project_info_response(deps, &env, project_id, project_info)
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListProjectsResponse { projects })
+}
+
+fn project_info_response(
+    deps: Deps,
+    env: &Env,
+    project_id: u64,
+    mut project_info: ProjectInfo,
+) -> StdResult<GetProjectInfoResponse> {
+    let token = token_of(TOKEN_CONFIGS.load(deps.storage, project_id)?);
+    let milestones = MILESTONES
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default();
+    let receipt_nft = RECEIPT_NFTS.may_load(deps.storage, project_id)?;
+    let platform_fee = CONFIG
+        .load(deps.storage)?
+        .platform_fee
+        .map(|platform_fee| platform_fee.fee)
+        .unwrap_or_default();
+    let merkle_root = MERKLE_ROOTS.may_load(deps.storage, project_id)?;
+    let pending_owner = PENDING_OWNERS.may_load(deps.storage, project_id)?;
+    let beneficiaries = BENEFICIARIES
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default();
+    let assets = project_assets(deps.storage, project_id)?
+        .into_iter()
+        .zip(raised_assets(deps.storage, project_id, &project_info)?)
+        .map(|(asset, (_, raised))| AssetResponse {
+            token: token_of(asset.token),
+            rate: asset.rate,
+            raised,
+        })
+        .collect();
+
+    let now: u64 = env.block.time.seconds();
+    let starts_in = project_info
+        .start_time
+        .filter(|start_time| now < *start_time)
+        .map(|start_time| start_time - now);
+    if starts_in.is_some() && project_info.status == Status::Ongoing {
+        project_info.status = Status::Upcoming;
+    }
+    if project_info.status == Status::Ongoing
+        && project_info.end_time <= now
+        && project_info.current_amount < project_info.target_amount
+    {
+        project_info.status = Status::Failed;
+    }
+
+    Ok(GetProjectInfoResponse {
+        project_id,
+        title: project_info.title,
+        description: project_info.description,
+        project_owner: project_info.project_owner,
+        token,
+        target_amount: project_info.target_amount,
+        max_amount: project_info.max_amount,
+        min_contribution: project_info.min_contribution,
+        max_contribution_per_address: project_info.max_contribution_per_address,
+        unpledge_penalty: project_info.unpledge_penalty,
+        referral_reward: project_info.referral_reward,
+        start_time: project_info.start_time,
+        starts_in,
+        end_time: project_info.end_time,
+        current_amount: project_info.current_amount,
+        withdrawn_amount: project_info.withdrawn_amount,
+        refunded_amount: project_info.refunded_amount,
+        status: project_info.status,
+        milestones,
+        platform_fee,
+        fee_amount: project_info.fee_amount,
+        receipt_nft,
+        merkle_root,
+        pending_owner,
+        beneficiaries,
+        assets,
+    })
+}
+
+fn query_contribution(
+    deps: Deps,
+    project_id: u64,
+    address: Addr,
+) -> StdResult<GetContributionResponse> {
+    let token = token_of(TOKEN_CONFIGS.load(deps.storage, project_id)?);
+
+    let contribution = CONTRIBUTIONS.may_load(deps.storage, (project_id, &address))?;
+    let contributed_amount = match contribution {
+        Some(amount) => amount,
+        None => Uint128::zero(),
+    };
+    let assets = contributed_assets(deps.storage, project_id, &address, contributed_amount)?
+        .into_iter()
+        .map(|(token_config, amount)| TokenAmount {
+            token: token_of(token_config),
+            amount,
+        })
+        .collect();
+    let refunded_amount = REFUNDS
+        .may_load(deps.storage, (project_id, &address))?
+        .unwrap_or_default();
+    let tier = BACKER_TIERS.may_load(deps.storage, (project_id, &address))?;
+    Ok(GetContributionResponse {
+        token,
+        amount: contributed_amount,
+        refunded_amount,
+        tier,
+        assets,
+    })
+}
+
+fn query_contribution_allowance(
+    deps: Deps,
+    project_id: u64,
+    address: Addr,
+) -> StdResult<GetContributionAllowanceResponse> {
+    let project_info = PROJECTS.load(deps.storage, project_id)?;
+    let contributed = CONTRIBUTIONS
+        .may_load(deps.storage, (project_id, &address))?
+        .unwrap_or_default();
+    let min_amount = project_info
+        .min_contribution
+        .unwrap_or_default()
+        .saturating_sub(contributed);
+    let remaining = project_info
+        .max_contribution_per_address
+        .map(|max| max.saturating_sub(contributed));
+    Ok(GetContributionAllowanceResponse {
+        contributed,
+        min_amount,
+        remaining,
+    })
+}
+
+fn referral_response(referral: Referral) -> ReferralResponse {
+    ReferralResponse {
+        referrer: referral.referrer,
+        amount: referral.amount,
+        rewarded: referral.rewarded,
+    }
+}
+
+fn query_referral(deps: Deps, project_id: u64, referrer: Addr) -> StdResult<ReferralResponse> {
+    let referral = referrals()
+        .may_load(deps.storage, (project_id, &referrer))?
+        .unwrap_or(Referral {
+            project_id,
+            referrer,
+            amount: Uint128::zero(),
+            rewarded: Uint128::zero(),
+        });
+    Ok(referral_response(referral))
+}
+
+fn proposal_response(proposal_id: u64, proposal: Proposal) -> ProposalResponse {
+    ProposalResponse {
+        proposal_id,
+        amount: proposal.amount,
+        purpose: proposal.purpose,
+        end_time: proposal.end_time,
+        yes: proposal.yes,
+        no: proposal.no,
+        status: proposal.status,
+    }
+}
+
+fn query_list_contributions(
+    deps: Deps,
+    project_id: u64,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<ListContributionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let contributions = CONTRIBUTIONS
+        .prefix(project_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(address, amount)| ContributionResponse { address, amount }))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListContributionsResponse { contributions })
+}
+
+fn query_contributor_count(deps: Deps, project_id: u64) -> StdResult<ContributorCountResponse> {
+    let count = CONTRIBUTOR_COUNT
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default();
+    Ok(ContributorCountResponse { count })
+}
+
+fn query_proposal(deps: Deps, project_id: u64, proposal_id: u64) -> StdResult<ProposalResponse> {
+    let proposal = PROPOSALS.load(deps.storage, (project_id, proposal_id))?;
+    Ok(proposal_response(proposal_id, proposal))
+}
+
+fn query_list_proposals(
+    deps: Deps,
+    project_id: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ListProposalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proposals = PROPOSALS
+        .prefix(project_id)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(proposal_id, proposal)| proposal_response(proposal_id, proposal)))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListProposalsResponse { proposals })
+}
+
+fn query_vesting(deps: Deps, env: Env, project_id: u64) -> StdResult<VestingResponse> {
+    let vesting = VESTINGS.load(deps.storage, project_id)?;
+    Ok(VestingResponse {
+        claimable: vested_amount(&vesting, env.block.time.seconds()) - vesting.claimed,
+        cliff: vesting.cliff,
+        duration: vesting.duration,
+        start_time: vesting.start_time,
+        amount: vesting.amount,
+        claimed: vesting.claimed,
+    })
+}
+
+fn query_matching_pool(deps: Deps, project_id: u64) -> StdResult<MatchingPoolResponse> {
+    let pool = MATCHING_POOLS.load(deps.storage, project_id)?;
+    Ok(MatchingPoolResponse {
+        remaining: pool.cap - pool.matched,
+        sponsor: pool.sponsor,
+        ratio: pool.ratio,
+        cap: pool.cap,
+        matched: pool.matched,
+        reclaimed: pool.reclaimed,
+    })
+}
+
+fn query_round(deps: Deps, round_id: u64) -> StdResult<RoundResponse> {
+    let round = ROUNDS.load(deps.storage, round_id)?;
+    Ok(RoundResponse {
+        round_id,
+        token: token_of(round.token),
+        project_ids: round.project_ids,
+        attestor: round.attestor,
+        end_time: round.end_time,
+        matching_pool: round.matching_pool,
+        status: round.status,
+        matches: round.matches,
+    })
+}
+
+fn query_round_estimates(deps: Deps, env: Env, round_id: u64) -> StdResult<RoundMatchesResponse> {
+    let round = ROUNDS.load(deps.storage, round_id)?;
+    let matches = match round.status {
+        RoundStatus::Open => {
+            round_matches(deps.storage, round_id, &round, env.block.time.seconds())?
+        }
+        _ => round.matches,
+    };
+    Ok(RoundMatchesResponse { matches })
+}
+
+fn query_top_referrers(
+    deps: Deps,
+    project_id: u64,
+    limit: Option<u32>,
+) -> StdResult<ListReferralsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let referrals = referrals()
+        .idx
+        .amount
+        .sub_prefix(project_id)
+        .range(deps.storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, referral)| referral_response(referral)))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListReferralsResponse { referrals })
+}
+
+fn query_receipt(deps: Deps, project_id: u64, token_id: String) -> StdResult<GetReceiptResponse> {
+    let receipt_nft = RECEIPT_NFTS.load(deps.storage, project_id)?;
+    let receipt = RECEIPTS.load(deps.storage, (project_id, &token_id))?;
+    let holder = receipt_holder(&deps.querier, &receipt_nft, &token_id)?;
+    Ok(GetReceiptResponse {
+        token_id,
+        backer: receipt.backer,
+        holder,
+        amount: receipt.amount,
+        tier: receipt.tier,
+        refunded: receipt.refunded,
+    })
+}
+
+fn query_reward_tiers(deps: Deps, project_id: u64) -> StdResult<GetRewardTiersResponse> {
+    let tiers = REWARD_TIERS
+        .may_load(deps.storage, project_id)?
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, tier)| RewardTierResponse {
+            index: index as u32,
+            available: tier.max_backers.map(|max| max.saturating_sub(tier.backers)),
+            name: tier.name,
+            min_amount: tier.min_amount,
+            max_backers: tier.max_backers,
+            backers: tier.backers,
+        })
+        .collect();
+    Ok(GetRewardTiersResponse { tiers })
+}
+
+fn query_legacy_contribution(
+    deps: Deps,
+    project_id: u64,
+) -> StdResult<GetLegacyContributionResponse> {
+    let (token, amount) = match TOKEN_CONFIGS.load(deps.storage, project_id)? {
+        TokenConfig::Native { denom } => (Token::Native { denom }, Uint128::zero()),
+        TokenConfig::CW20 { addr } => {
+            let amount = CONTRIBUTIONS
+                .may_load(deps.storage, (project_id, &addr))?
+                .unwrap_or_default();
+            (Token::CW20 { addr }, amount)
+        }
+    };
+    Ok(GetLegacyContributionResponse { token, amount })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{AcceptedAssetMsg, GovernanceMsg, MilestoneMsg, RewardTierMsg, VestingMsg};
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_dependencies_with_balance, mock_env, mock_info,
+    };
+    use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coin, coins, from_binary, ContractResult, OwnedDeps, SystemResult, WasmQuery,
+    };
+
+    const OWNER: &str = "owner";
+    const DENOM: &str = "ujuno";
+    const PROJECT_ID: u64 = 1;
+
+    fn legacy_v0_1_project() -> migrations::v0_1::ProjectInfo {
+        migrations::v0_1::ProjectInfo {
+            title: "legacy".into(),
+            description: "deployed with v0.1.0".into(),
+            project_owner: Addr::unchecked(OWNER),
+            target_amount: Uint128::new(100),
+            end_time: mock_env().block.time.seconds() + 100,
+            current_amount: Uint128::new(40),
+            status: Status::Ongoing,
+        }
+    }
+
+    fn migrate_msg(refunded: &[&str]) -> MigrateMsg {
+        MigrateMsg {
+            admin: None,
+            refunded: refunded.iter().map(|addr| addr.to_string()).collect(),
+        }
+    }
+
+    fn query_project(deps: Deps, project_id: u64) -> GetProjectInfoResponse {
+        let res = query(deps, mock_env(), QueryMsg::GetProjectInfo { project_id }).unwrap();
+        from_binary(&res).unwrap()
+    }
+
+    fn query_contributed(deps: Deps, project_id: u64, address: &str) -> GetContributionResponse {
+        let msg = QueryMsg::GetContribution {
+            project_id,
+            address: Addr::unchecked(address),
+        };
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn migrate_from_v0_1() {
+        // v0.1.0 holds everything raised so far
+        let mut deps = mock_dependencies_with_balance(&coins(40, DENOM));
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        migrations::v0_2::TOKEN_CONFIG
+            .save(deps.as_mut().storage, &TokenConfig::Native { denom: DENOM.into() })
+            .unwrap();
+        migrations::v0_1::PROJECT_INFO
+            .save(deps.as_mut().storage, &legacy_v0_1_project())
+            .unwrap();
+        migrations::v0_2::CONTRIBUTIONS
+            .save(deps.as_mut().storage, &Addr::unchecked("backer"), &Uint128::new(40))
+            .unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap();
+        assert_eq!(res.attributes[1].value, "0.1.0");
+        assert_eq!(
+            get_contract_version(deps.as_ref().storage).unwrap().version,
+            CONTRACT_VERSION
+        );
+
+        // admin falls back to project owner
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.admin, Addr::unchecked(OWNER));
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.title, "legacy");
+        assert_eq!(project.current_amount, Uint128::new(40));
+        assert_eq!(project.withdrawn_amount, Uint128::zero());
+        assert_eq!(project.refunded_amount, Uint128::zero());
+        assert_eq!(project.status, Status::Ongoing);
+
+        // migrated project keeps accepting contributions
+        let info = mock_info("backer", &coins(60, DENOM));
+        let msg = ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier: None,
+            proof: None,
+            referrer: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let contribution = query_contributed(deps.as_ref(), PROJECT_ID, "backer");
+        assert_eq!(contribution.amount, Uint128::new(100));
+    }
+
+    #[test]
+    fn migrate_from_v0_1_with_admin() {
+        // v0.1.0 holds everything raised so far
+        let mut deps = mock_dependencies_with_balance(&coins(40, DENOM));
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        migrations::v0_2::TOKEN_CONFIG
+            .save(deps.as_mut().storage, &TokenConfig::Native { denom: DENOM.into() })
+            .unwrap();
+        migrations::v0_1::PROJECT_INFO
+            .save(deps.as_mut().storage, &legacy_v0_1_project())
+            .unwrap();
+
+        let msg = MigrateMsg {
+            admin: Some("admin".into()),
+            refunded: vec![],
+        };
+        migrate(deps.as_mut(), mock_env(), msg).unwrap();
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.admin, Addr::unchecked("admin"));
+    }
+
+    // ended v0.1.0 project with contributions of backer and backer2, holding balance
+    fn ended_v0_1_project(
+        status: Status,
+        current_amount: u128,
+        balance: u128,
+    ) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies_with_balance(&coins(balance, DENOM));
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        migrations::v0_2::TOKEN_CONFIG
+            .save(deps.as_mut().storage, &TokenConfig::Native { denom: DENOM.into() })
+            .unwrap();
+        let mut project_info = legacy_v0_1_project();
+        project_info.end_time = mock_env().block.time.seconds() - 1;
+        project_info.current_amount = Uint128::new(current_amount);
+        project_info.status = status;
+        migrations::v0_1::PROJECT_INFO
+            .save(deps.as_mut().storage, &project_info)
+            .unwrap();
+        let backer = current_amount - 10;
+        for (addr, amount) in [("backer", backer), ("backer2", 10)] {
+            migrations::v0_2::CONTRIBUTIONS
+                .save(deps.as_mut().storage, &Addr::unchecked(addr), &Uint128::new(amount))
+                .unwrap();
+        }
+        deps
+    }
+
+    #[test]
+    fn migrate_from_v0_1_withdrawn() {
+        // owner withdrew under v0.1.0, which left no trace but the balance
+        let mut deps = ended_v0_1_project(Status::Succeeded, 100, 0);
+        migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap();
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.status, Status::Withdrawn);
+        assert_eq!(project.withdrawn_amount, Uint128::new(100));
+
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyWithdrawn {});
+        let msg = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("backer", &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotFailed {});
+    }
+
+    #[test]
+    fn migrate_from_v0_1_refunded() {
+        // backer took 30 back under v0.1.0
+        let mut deps = ended_v0_1_project(Status::Ongoing, 40, 10);
+        let err = migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::LegacyBalanceShort {
+                owed: Uint128::new(40),
+                balance: Uint128::new(10)
+            }
+        );
+        let err = migrate(deps.as_mut(), mock_env(), migrate_msg(&["stranger"])).unwrap_err();
+        assert_eq!(err, ContractError::NoContribution {});
+
+        migrate(deps.as_mut(), mock_env(), migrate_msg(&["backer"])).unwrap();
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.status, Status::Refunding);
+        assert_eq!(project.refunded_amount, Uint128::new(30));
+
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, refund.clone()).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+        let res = execute(deps.as_mut(), mock_env(), mock_info("backer2", &[]), refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer2".into(),
+                amount: coins(10, DENOM),
+            })
+        );
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.refunded_amount, project.current_amount);
+    }
+
+    #[test]
+    fn migrate_from_v0_2() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.2.0").unwrap();
+        CONFIG
+            .save(
+                deps.as_mut().storage,
+                &Config {
+                    admin: Addr::unchecked("admin"),
+                    platform_fee: None,
+                },
+            )
+            .unwrap();
+        migrations::v0_2::TOKEN_CONFIG
+            .save(deps.as_mut().storage, &TokenConfig::CW20 { addr: Addr::unchecked("cw20") })
+            .unwrap();
+        let legacy = legacy_v0_1_project();
+        let project_info = migrations::v0_2::ProjectInfo {
+            title: legacy.title,
+            description: legacy.description,
+            project_owner: legacy.project_owner,
+            target_amount: legacy.target_amount,
+            end_time: legacy.end_time,
+            current_amount: legacy.current_amount,
+            withdrawn_amount: Uint128::zero(),
+            refunded_amount: Uint128::new(10),
+            status: Status::Refunding,
+        };
+        migrations::v0_2::PROJECT_INFO
+            .save(deps.as_mut().storage, &project_info)
+            .unwrap();
+        // cw20 contributions v0.1.0 credited to the token address are no contributor
+        for (addr, amount) in [("backer", 30), ("cw20", 10), ("nobody", 0)] {
+            migrations::v0_2::CONTRIBUTIONS
+                .save(deps.as_mut().storage, &Addr::unchecked(addr), &Uint128::new(amount))
+                .unwrap();
+        }
+        migrations::v0_2::REFUNDS
+            .save(deps.as_mut().storage, &Addr::unchecked("refunded"), &Uint128::new(10))
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap();
+
+        // admin set by v0.2.0 is kept
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.admin, Addr::unchecked("admin"));
+
+        // single project became project 1
+        assert_eq!(PROJECT_COUNT.load(deps.as_ref().storage).unwrap(), PROJECT_ID);
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.token, Token::CW20 { addr: Addr::unchecked("cw20") });
+        assert_eq!(project.refunded_amount, Uint128::new(10));
+        assert_eq!(project.status, Status::Refunding);
+        assert!(migrations::v0_2::PROJECT_INFO
+            .may_load(deps.as_ref().storage)
+            .unwrap()
+            .is_none());
+
+        let contribution = query_contributed(deps.as_ref(), PROJECT_ID, "backer");
+        assert_eq!(contribution.amount, Uint128::new(30));
+        let contribution = query_contributed(deps.as_ref(), PROJECT_ID, "refunded");
+        assert_eq!(contribution.refunded_amount, Uint128::new(10));
+        assert!(migrations::v0_2::CONTRIBUTIONS
+            .may_load(deps.as_ref().storage, &Addr::unchecked("backer"))
+            .unwrap()
+            .is_none());
+        let msg = QueryMsg::GetContributorCount { project_id: PROJECT_ID };
+        let res: ContributorCountResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.count, 1);
+        // rounds count the migrated backer only, isqrt(30) = 5
+        let sqrt_sum = SQRT_SUMS.load(deps.as_ref().storage, PROJECT_ID).unwrap();
+        assert_eq!(sqrt_sum, Uint128::new(5));
+        let key = (PROJECT_ID, &Addr::unchecked("nobody"));
+        assert!(!CONTRIBUTIONS.has(deps.as_ref().storage, key));
+        let msg = QueryMsg::GetLegacyContribution { project_id: PROJECT_ID };
+        let res: GetLegacyContributionResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.amount, Uint128::new(10));
+
+        // new projects are numbered after the migrated one
+        let project = project_msg(Token::Native { denom: DENOM.into() });
+        let msg = ExecuteMsg::CreateProject(Box::new(project));
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        assert_eq!(query_project(deps.as_ref(), 2).project_id, 2);
+    }
+
+    #[test]
+    fn migrate_rejects_other_contract_and_downgrade() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrate {
+                contract: "crates.io:cw20-base".into()
+            }
+        );
+
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), migrate_msg(&[])).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotDowngrade {
+                from: "99.0.0".into(),
+                to: CONTRACT_VERSION.into()
+            }
+        );
+    }
+
+    fn project_msg(token: Token) -> ProjectMsg {
+        ProjectMsg {
+            title: "title".into(),
+            description: "description".into(),
+            token,
+            target_amount: Uint128::new(100),
+            max_amount: None,
+            min_contribution: None,
+            max_contribution_per_address: None,
+            unpledge_penalty: 0,
+            referral_reward: 0,
+            start_time: None,
+            end_time: mock_env().block.time.seconds() + 100,
+            milestones: vec![],
+            reward_tiers: vec![],
+            merkle_root: None,
+            accepted_assets: vec![],
+            receipt_nft: None,
+            vesting: None,
+            governance: None,
+            beneficiaries: vec![],
+        }
+    }
+
+    fn instantiate_with(deps: DepsMut, token: Token) {
+        instantiate_project(deps, project_msg(token)).unwrap();
+    }
+
+    // instantiates with a single project created by OWNER
+    fn instantiate_project(deps: DepsMut, project: ProjectMsg) -> Result<Response, ContractError> {
+        let msg = InstantiateMsg {
+            admin: None,
+            project: Some(project),
+            project_owner: None,
+            platform_fee: None,
+        };
+        instantiate(deps, mock_env(), mock_info(OWNER, &[]), msg)
+    }
+
+    fn ended_env() -> Env {
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        env
+    }
+
+    fn contribute_msg() -> ExecuteMsg {
+        ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier: None,
+            proof: None,
+            referrer: None,
+        }
+    }
+
+    fn cw20_contribution(sender: &str, amount: u128) -> ExecuteMsg {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.into(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::Contribute {
+                project_id: PROJECT_ID,
+                tier: None,
+                proof: None,
+                referrer: None,
+            })
+            .unwrap(),
+        })
+    }
+
+    #[test]
+    fn multiple_projects() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: None,
+            project: None,
+            project_owner: None,
+            platform_fee: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("admin", &[]), msg).unwrap();
+
+        let project = project_msg(Token::Native { denom: DENOM.into() });
+        let msg = ExecuteMsg::CreateProject(Box::new(project));
+        let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        assert_eq!(res.attributes[1].value, "1");
+        let msg = ExecuteMsg::CreateProject(Box::new(project_msg(Token::CW20 {
+            addr: Addr::unchecked("cw20"),
+        })));
+        let res = execute(deps.as_mut(), mock_env(), mock_info("other", &[]), msg).unwrap();
+        assert_eq!(res.attributes[1].value, "2");
+
+        // contributions are kept per project
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let mut msg = cw20_contribution("backer", 10);
+        if let ExecuteMsg::Receive(wrapped) = &mut msg {
+            wrapped.msg = to_binary(&ReceiveMsg::Contribute {
+                project_id: 2,
+                tier: None,
+                proof: None,
+                referrer: None,
+            })
+            .unwrap();
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("cw20", &[]), msg).unwrap();
+
+        assert_eq!(query_contributed(deps.as_ref(), 1, "backer").amount, Uint128::new(100));
+        assert_eq!(query_contributed(deps.as_ref(), 2, "backer").amount, Uint128::new(10));
+        assert_eq!(query_project(deps.as_ref(), 1).status, Status::Succeeded);
+        assert_eq!(query_project(deps.as_ref(), 2).status, Status::Ongoing);
+
+        let msg = QueryMsg::ListProjects { start_after: Some(1), limit: None };
+        let res: ListProjectsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.projects.len(), 1);
+        assert_eq!(res.projects[0].project_owner, Addr::unchecked("other"));
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        let msg = ExecuteMsg::Contribute {
+            project_id: 3,
+            tier: None,
+            proof: None,
+            referrer: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::ProjectNotFound { project_id: 3 });
+    }
+
+    #[test]
+    fn contribute_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+
+        let info = mock_info(OWNER, &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::OwnerCannotContribute {});
+
+        let info = mock_info("backer", &coins(10, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::WrongDenom { expected: DENOM.into() });
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::WrongDenom { expected: DENOM.into() });
+
+        // a second coin would be kept without being credited
+        let info = mock_info("backer", &[coin(10, DENOM), coin(10, "uatom")]);
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::MultipleDenoms {});
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), ended_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::ProjectEnded {});
+
+        let info = mock_info("cw20", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 10))
+            .unwrap_err();
+        assert_eq!(err, ContractError::NativeOnly {});
+    }
+
+    #[test]
+    fn cw20_contribute_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::CW20 { addr: Addr::unchecked("cw20") });
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::Cw20Only {});
+
+        let info = mock_info("other_cw20", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 10))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WrongCw20Token {
+                expected: Addr::unchecked("cw20")
+            }
+        );
+
+        let info = mock_info("cw20", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cw20_contribution(OWNER, 10))
+            .unwrap_err();
+        assert_eq!(err, ContractError::OwnerCannotContribute {});
+    }
+
+    #[test]
+    fn withdraw_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let withdraw = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let info = mock_info(OWNER, &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ProjectNotEnded {});
+
+        let info = mock_info(OWNER, &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, withdraw.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NotSucceeded {});
+
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info(OWNER, &[]);
+        let res = execute(deps.as_mut(), ended_env(), info, withdraw.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let info = mock_info(OWNER, &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, withdraw).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyWithdrawn {});
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund).unwrap_err();
+        assert_eq!(err, ContractError::NotFailed {});
+    }
+
+    #[test]
+    fn refund_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, refund.clone()).unwrap_err();
+        assert_eq!(err, ContractError::ProjectNotEnded {});
+
+        let info = mock_info("stranger", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund.clone()).unwrap_err();
+        assert_eq!(err, ContractError::NoContribution {});
+
+        let info = mock_info("backer", &[]);
+        let res = execute(deps.as_mut(), ended_env(), info, refund.clone()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+    }
+
+    #[test]
+    fn no_double_payouts() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let project = project_msg(Token::Native { denom: DENOM.into() });
+        let msg = ExecuteMsg::CreateProject(Box::new(project));
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        // both projects hold their funds in the same denom
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        for (backer, amount) in [("backer2", 40), ("backer3", 30)] {
+            let msg = ExecuteMsg::Contribute {
+                project_id: 2,
+                tier: None,
+                proof: None,
+                referrer: None,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info(backer, &coins(amount, DENOM)), msg)
+                .unwrap();
+        }
+
+        let withdraw = |project_id| ExecuteMsg::Withdraw { project_id };
+        let refund = |project_id| ExecuteMsg::Refund {
+            project_id,
+            receipt: None,
+        };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw(1)).unwrap();
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw(1))
+            .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyWithdrawn {});
+        let err = execute(deps.as_mut(), ended_env(), mock_info("backer", &[]), refund(1))
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotFailed {});
+        let project = query_project(deps.as_ref(), 1);
+        assert_eq!(project.status, Status::Withdrawn);
+        assert_eq!(project.withdrawn_amount, Uint128::new(100));
+
+        execute(deps.as_mut(), ended_env(), mock_info("backer2", &[]), refund(2)).unwrap();
+        let err = execute(deps.as_mut(), ended_env(), mock_info("backer2", &[]), refund(2))
+            .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw(2))
+            .unwrap_err();
+        assert_eq!(err, ContractError::NotSucceeded {});
+        execute(deps.as_mut(), ended_env(), mock_info("backer3", &[]), refund(2)).unwrap();
+
+        // refunds never reach into the funds of project 1
+        let project = query_project(deps.as_ref(), 2);
+        assert_eq!(project.status, Status::Refunding);
+        assert_eq!(project.refunded_amount, project.current_amount);
+    }
+
+    #[test]
+    fn reassign_legacy_contribution_errors() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::CW20 { addr: Addr::unchecked("cw20") });
+        CONTRIBUTIONS
+            .save(
+                deps.as_mut().storage,
+                (PROJECT_ID, &Addr::unchecked("cw20")),
+                &Uint128::new(30),
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::ReassignLegacyContribution {
+            project_id: PROJECT_ID,
+            recipient: "backer".into(),
+            amount: Uint128::new(10),
+        };
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let msg = ExecuteMsg::ReassignLegacyContribution {
+            project_id: PROJECT_ID,
+            recipient: "cw20".into(),
+            amount: Uint128::new(10),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidReassignRecipient {
+                recipient: Addr::unchecked("cw20")
+            }
+        );
+
+        let msg = ExecuteMsg::ReassignLegacyContribution {
+            project_id: PROJECT_ID,
+            recipient: "backer".into(),
+            amount: Uint128::new(31),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientLegacyContribution {
+                available: Uint128::new(30)
+            }
+        );
+    }
+
+    #[test]
+    fn reassign_legacy_contribution() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::CW20 { addr: Addr::unchecked("cw20") });
+
+        // cw20 contributions are credited to the backer, never to the token contract
+        let info = mock_info("cw20", &[]);
+        execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 20)).unwrap();
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").amount, Uint128::new(20));
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "cw20").amount, Uint128::zero());
+
+        // what v0.1.0 credited to the token address
+        let legacy_key = (PROJECT_ID, &Addr::unchecked("cw20"));
+        CONTRIBUTIONS
+            .save(deps.as_mut().storage, legacy_key, &Uint128::new(40))
+            .unwrap();
+        PROJECTS
+            .update(deps.as_mut().storage, PROJECT_ID, |project| -> StdResult<_> {
+                let mut project = project.unwrap();
+                project.current_amount += Uint128::new(40);
+                Ok(project)
+            })
+            .unwrap();
+        let contributor_count = |deps: Deps| {
+            let msg = QueryMsg::GetContributorCount { project_id: PROJECT_ID };
+            let res: ContributorCountResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.count
+        };
+        assert_eq!(contributor_count(deps.as_ref()), 1);
+
+        let reassign = |recipient: &str, amount| ExecuteMsg::ReassignLegacyContribution {
+            project_id: PROJECT_ID,
+            recipient: recipient.into(),
+            amount: Uint128::new(amount),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), reassign("alice", 30)).unwrap();
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "alice").amount, Uint128::new(30));
+        assert_eq!(contributor_count(deps.as_ref()), 2);
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), reassign("backer", 10)).unwrap();
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").amount, Uint128::new(30));
+        assert!(!CONTRIBUTIONS.has(deps.as_ref().storage, legacy_key));
+        assert_eq!(contributor_count(deps.as_ref()), 2);
+
+        // project failed, the new holder gets the reassigned amount back
+        let msg = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "cw20".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "alice".into(),
+                    amount: Uint128::new(30),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let contribution = query_contributed(deps.as_ref(), PROJECT_ID, "alice");
+        assert_eq!(contribution.refunded_amount, Uint128::new(30));
+        assert_eq!(contributor_count(deps.as_ref()), 1);
+
+        // refunded address cannot be credited again
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), reassign("alice", 1))
+            .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+    }
+
+    #[test]
+    fn milestone_release_and_abandon() {
+        let mut deps = mock_dependencies();
+        let end_time = mock_env().block.time.seconds() + 100;
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.milestones = vec![
+            MilestoneMsg {
+                title: "prototype".into(),
+                percentage: 40,
+                unlock_time: end_time,
+            },
+            MilestoneMsg {
+                title: "release".into(),
+                percentage: 60,
+                unlock_time: end_time + 50,
+            },
+        ];
+        instantiate_project(deps.as_mut(), project.clone()).unwrap();
+
+        let info = mock_info("backer", &coins(70, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer2", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        let info = mock_info(OWNER, &[]);
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::MilestonesConfigured {});
+
+        let release = |index| ExecuteMsg::ReleaseMilestone {
+            project_id: PROJECT_ID,
+            index,
+        };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), release(1))
+            .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneLocked { unlock_time: end_time + 50 });
+
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), release(0)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(40, DENOM),
+            })
+        );
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), release(0))
+            .unwrap_err();
+        assert_eq!(err, ContractError::MilestoneAlreadyReleased { index: 0 });
+
+        // backers only get back what was not released yet
+        let msg = ExecuteMsg::Abandon { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), mock_info("backer", &[]), msg.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("backer", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer".into(),
+                amount: coins(42, DENOM),
+            })
+        );
+        let project_info = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project_info.status, Status::Abandoned);
+        assert!(project_info.milestones[0].released);
+
+        // what is left belongs to the backers once abandoned
+        let mut env = ended_env();
+        env.block.time = env.block.time.plus_seconds(50);
+        let err = execute(deps.as_mut(), env, mock_info(OWNER, &[]), release(1)).unwrap_err();
+        assert_eq!(err, ContractError::NotSucceeded {});
+
+        // invalid milestone split is rejected
+        project.milestones[1].percentage = 50;
+        let msg = ExecuteMsg::CreateProject(Box::new(project));
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMilestones {});
+    }
+
+    #[test]
+    fn reward_tiers() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.reward_tiers = vec![
+            RewardTierMsg {
+                name: "basic".into(),
+                min_amount: Uint128::new(10),
+                max_backers: None,
+            },
+            RewardTierMsg {
+                name: "gold".into(),
+                min_amount: Uint128::new(50),
+                max_backers: Some(1),
+            },
+        ];
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let contribute = |tier| ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier: Some(tier),
+            proof: None,
+            referrer: None,
+        };
+        let info = mock_info("backer", &coins(20, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(1)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::BelowTierMinimum {
+                index: 1,
+                min_amount: Uint128::new(50)
+            }
+        );
+        let info = mock_info("backer", &coins(20, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(2)).unwrap_err();
+        assert_eq!(err, ContractError::TierNotFound { index: 2 });
+
+        // minimum counts everything the backer contributed so far
+        let info = mock_info("backer", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(1)).unwrap();
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").tier, Some(1));
+
+        let info = mock_info("backer2", &coins(60, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(1)).unwrap_err();
+        assert_eq!(err, ContractError::TierSoldOut { index: 1 });
+
+        // moving to another tier frees the slot
+        let info = mock_info("backer", &coins(1, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(0)).unwrap();
+        let info = mock_info("backer2", &coins(60, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(1)).unwrap();
+
+        let msg = QueryMsg::GetRewardTiers { project_id: PROJECT_ID };
+        let res: GetRewardTiersResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.tiers[0].backers, 1);
+        assert_eq!(res.tiers[0].available, None);
+        assert_eq!(res.tiers[1].backers, 1);
+        assert_eq!(res.tiers[1].available, Some(0));
+    }
+
+    #[test]
+    fn receipt_holder_refunds() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.receipt_nft = Some("receipts".into());
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        // every contribution mints a receipt
+        let info = mock_info("backer", &coins(40, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // receipt 1-1 got sold to buyer
+        deps.querier.update_wasm(|_| {
+            let res = OwnerOfResponse {
+                owner: "buyer".into(),
+                approvals: vec![],
+            };
+            SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+        });
+        let msg = QueryMsg::GetReceipt {
+            project_id: PROJECT_ID,
+            token_id: "1-1".into(),
+        };
+        let receipt: GetReceiptResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(receipt.backer, Addr::unchecked("backer"));
+        assert_eq!(receipt.holder, Addr::unchecked("buyer"));
+        assert_eq!(receipt.amount, Uint128::new(40));
+
+        let refund = |receipt: Option<&str>| ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: receipt.map(String::from),
+        };
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund(None)).unwrap_err();
+        assert_eq!(err, ContractError::ReceiptRequired {});
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund(Some("1-1"))).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund(Some("1-3"))).unwrap_err();
+        assert_eq!(err, ContractError::ReceiptNotFound { token_id: "1-3".into() });
+
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), ended_env(), info, refund(Some("1-1"))).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "buyer".into(),
+                amount: coins(40, DENOM),
+            })
+        );
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").amount, Uint128::new(10));
+
+        let info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, refund(Some("1-1"))).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+    }
+
+    #[test]
+    fn receipt_tiers() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.receipt_nft = Some("receipts".into());
+        project.reward_tiers = vec![RewardTierMsg {
+            name: "gold".into(),
+            min_amount: Uint128::new(50),
+            max_backers: Some(2),
+        }];
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let contribute = |tier| ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier,
+            proof: None,
+            referrer: None,
+        };
+
+        // earlier receipts may belong to someone else, only this contribution counts
+        let info = mock_info("backer", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(None)).unwrap();
+        let info = mock_info("backer", &coins(30, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(Some(0))).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::BelowTierMinimum {
+                index: 0,
+                min_amount: Uint128::new(50)
+            }
+        );
+        let info = mock_info("backer", &coins(50, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some(0))).unwrap();
+
+        // the tier went with its receipt, later receipts do not inherit it
+        let info = mock_info("backer", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(None)).unwrap();
+        let tier = |token_id: &str| {
+            RECEIPTS
+                .load(deps.as_ref().storage, (PROJECT_ID, token_id))
+                .unwrap()
+                .tier
+        };
+        assert_eq!(tier("1-2"), Some(0));
+        assert_eq!(tier("1-3"), None);
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").tier, None);
+
+        // every receipt takes its own slot
+        let info = mock_info("backer", &coins(50, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some(0))).unwrap();
+        let info = mock_info("backer2", &coins(50, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(Some(0))).unwrap_err();
+        assert_eq!(err, ContractError::TierSoldOut { index: 0 });
+    }
+
+    #[test]
+    fn reassigned_contribution_gets_receipt() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::CW20 { addr: Addr::unchecked("cw20") });
+        project.receipt_nft = Some("receipts".into());
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let legacy_key = (PROJECT_ID, &Addr::unchecked("cw20"));
+        CONTRIBUTIONS
+            .save(deps.as_mut().storage, legacy_key, &Uint128::new(40))
+            .unwrap();
+        PROJECTS
+            .update(deps.as_mut().storage, PROJECT_ID, |project| -> StdResult<_> {
+                let mut project = project.unwrap();
+                project.current_amount = Uint128::new(40);
+                Ok(project)
+            })
+            .unwrap();
+
+        let msg = ExecuteMsg::ReassignLegacyContribution {
+            project_id: PROJECT_ID,
+            recipient: "alice".into(),
+            amount: Uint128::new(40),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        let receipt = RECEIPTS.load(deps.as_ref().storage, (PROJECT_ID, "1-1")).unwrap();
+        assert_eq!(receipt.backer, Addr::unchecked("alice"));
+        assert_eq!(receipt.amount, Uint128::new(40));
+
+        // project failed, the reassigned balance is refundable through its receipt
+        deps.querier.update_wasm(|_| {
+            let res = OwnerOfResponse {
+                owner: "alice".into(),
+                approvals: vec![],
+            };
+            SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+        });
+        let msg = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: Some("1-1".into()),
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "cw20".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "alice".into(),
+                    amount: Uint128::new(40),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn hard_cap() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.max_amount = Some(Uint128::new(90));
+        let msg = ExecuteMsg::CreateProject(Box::new(project.clone()));
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMaxAmount {});
+
+        project.max_amount = Some(Uint128::new(150));
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let info = mock_info("backer", &coins(120, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // overflow goes straight back
+        let info = mock_info("backer2", &coins(50, DENOM));
+        let res = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer2".into(),
+                amount: coins(20, DENOM),
+            })
+        );
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer2").amount, Uint128::new(30));
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.current_amount, Uint128::new(150));
+        assert_eq!(project.status, Status::Succeeded);
+
+        let info = mock_info("backer3", &coins(1, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::HardCapReached {});
+
+        // closed early, owner does not wait for end_time
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+    }
+
+    #[test]
+    fn hard_cap_with_assets() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.max_amount = Some(Uint128::new(101));
+        project.accepted_assets = vec![
+            AcceptedAssetMsg {
+                token: Token::Native { denom: "uusdc".into() },
+                rate: Decimal::percent(50),
+            },
+            AcceptedAssetMsg {
+                token: Token::CW20 { addr: Addr::unchecked("cw20") },
+                rate: Decimal::percent(200),
+            },
+        ];
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let info = mock_info("backer", &coins(1, "uusdc"));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::ZeroContribution {});
+
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // room of 1 is less than a single cw20 unit worth 2
+        let info = mock_info("cw20", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer2", 5))
+            .unwrap_err();
+        assert_eq!(err, ContractError::HardCapReached {});
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer2").amount, Uint128::zero());
+        let msg = QueryMsg::GetContributorCount { project_id: PROJECT_ID };
+        let res: ContributorCountResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.count, 1);
+
+        // but two uusdc still fit
+        let info = mock_info("backer2", &coins(10, "uusdc"));
+        let res = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer2".into(),
+                amount: coins(8, "uusdc"),
+            })
+        );
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).current_amount, Uint128::new(101));
+    }
+
+    #[test]
+    fn contribution_limits() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.min_contribution = Some(Uint128::new(10));
+        project.max_contribution_per_address = Some(Uint128::new(50));
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let info = mock_info("backer", &coins(5, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::BelowMinContribution {
+                min_contribution: Uint128::new(10)
+            }
+        );
+
+        // limits are cumulative, small top ups are fine once above the minimum
+        let info = mock_info("backer", &coins(40, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer", &coins(5, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        let info = mock_info("backer", &coins(6, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::AboveMaxContribution {
+                remaining: Uint128::new(5)
+            }
+        );
+
+        let msg = QueryMsg::GetContributionAllowance {
+            project_id: PROJECT_ID,
+            address: Addr::unchecked("backer"),
+        };
+        let res: GetContributionAllowanceResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.contributed, Uint128::new(45));
+        assert_eq!(res.min_amount, Uint128::zero());
+        assert_eq!(res.remaining, Some(Uint128::new(5)));
+    }
+
+    #[test]
+    fn upcoming_project() {
+        let mut deps = mock_dependencies();
+        let start_time = mock_env().block.time.seconds() + 50;
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.start_time = Some(project.end_time);
+        let msg = ExecuteMsg::CreateProject(Box::new(project.clone()));
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidStartTime {});
+
+        project.start_time = Some(start_time);
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.status, Status::Upcoming);
+        assert_eq!(project.starts_in, Some(50));
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::ProjectNotStarted { start_time });
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(50);
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), env.clone(), info, contribute_msg()).unwrap();
+
+        let msg = QueryMsg::GetProjectInfo { project_id: PROJECT_ID };
+        let project: GetProjectInfoResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(project.status, Status::Ongoing);
+        assert_eq!(project.starts_in, None);
+    }
+
+    #[test]
+    fn cancel_opens_refunds() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let cancel = ExecuteMsg::Cancel { project_id: PROJECT_ID };
+
+        // target reached, refunds would normally be closed
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, cancel.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), cancel.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), cancel).unwrap_err();
+        assert_eq!(err, ContractError::ProjectCancelled {});
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).status, Status::Cancelled);
+
+        let info = mock_info("backer2", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::ProjectCancelled {});
+
+        // no need to wait for end_time
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let info = mock_info("backer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer".into(),
+                amount: coins(100, DENOM),
+            })
+        );
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).status, Status::Cancelled);
+
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NotSucceeded {});
+    }
+
+    #[test]
+    fn unpledge() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.unpledge_penalty = 10;
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let unpledge = |amount| ExecuteMsg::Unpledge {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(amount),
+            asset: None,
+        };
+
+        let info = mock_info("backer", &coins(120, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).status, Status::Succeeded);
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, unpledge(121)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientContribution {
+                available: Uint128::new(120)
+            }
+        );
+
+        // penalty goes to project owner, dropping below target reopens the project
+        let info = mock_info("backer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, unpledge(50)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer".into(),
+                amount: coins(45, DENOM),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(5, DENOM),
+            })
+        );
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.current_amount, Uint128::new(70));
+        assert_eq!(project.status, Status::Ongoing);
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").amount, Uint128::new(70));
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, unpledge(0)).unwrap_err();
+        assert_eq!(err, ContractError::ZeroAmount {});
+
+        // penalty pays platform fee and is split among beneficiaries like any payout
+        let mut config = CONFIG.load(deps.as_ref().storage).unwrap();
+        config.platform_fee = Some(PlatformFee {
+            treasury: Addr::unchecked("treasury"),
+            fee: Decimal::percent(50),
+        });
+        CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        let msg = ExecuteMsg::UpdateBeneficiaries {
+            project_id: PROJECT_ID,
+            beneficiaries: vec![
+                BeneficiaryMsg {
+                    address: "alice".into(),
+                    percentage: 50,
+                },
+                BeneficiaryMsg {
+                    address: "bob".into(),
+                    percentage: 50,
+                },
+            ],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let info = mock_info("backer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, unpledge(60)).unwrap();
+        let sent: Vec<_> = res
+            .messages
+            .iter()
+            .map(|msg| match &msg.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    (to_address.as_str(), amount[0].amount.u128())
+                }
+                _ => panic!("unexpected message"),
+            })
+            .collect();
+        assert_eq!(sent, vec![("backer", 54), ("treasury", 3), ("alice", 1), ("bob", 2)]);
+
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, unpledge(10)).unwrap_err();
+        assert_eq!(err, ContractError::ProjectEnded {});
+    }
+
+    #[test]
+    fn allowlist() {
+        let leaf = |leaf: &str| -> [u8; 32] {
+            Sha256::new()
+                .chain_update([0])
+                .chain_update(leaf.as_bytes())
+                .finalize()
+                .into()
+        };
+        let leaf_a = leaf("backer");
+        let leaf_b = leaf("backer2:30");
+        let mut pair = [leaf_a, leaf_b];
+        pair.sort();
+        let root = Sha256::new().chain_update([1]).chain_update(pair.concat()).finalize();
+
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.merkle_root = Some(hex::encode(root));
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let contribute = |sibling: [u8; 32], cap: Option<u128>| ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier: None,
+            proof: Some(AllowlistProof {
+                proof: vec![hex::encode(sibling)],
+                cap: cap.map(Uint128::new),
+            }),
+            referrer: None,
+        };
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(err, ContractError::NotAllowlisted {});
+        let info = mock_info("stranger", &coins(10, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(leaf_b, None)).unwrap_err();
+        assert_eq!(err, ContractError::NotAllowlisted {});
+
+        // address and cap can't be shifted into one another
+        let info = mock_info("backer", &coins(10, DENOM));
+        let err =
+            execute(deps.as_mut(), mock_env(), info, contribute(leaf_b, Some(230))).unwrap_err();
+        assert_eq!(err, ContractError::NotAllowlisted {});
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(leaf_b, None)).unwrap();
+
+        // cap in the leaf can't be changed
+        let info = mock_info("backer2", &coins(40, DENOM));
+        let msg = contribute(leaf_a, Some(40));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NotAllowlisted {});
+        let info = mock_info("backer2", &coins(40, DENOM));
+        let msg = contribute(leaf_a, Some(30));
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::AboveMaxContribution {
+                remaining: Uint128::new(30)
+            }
+        );
+        let info = mock_info("backer2", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(leaf_a, Some(30))).unwrap();
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).current_amount, Uint128::new(40));
+    }
+
+    #[test]
+    fn multiple_assets() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.accepted_assets = vec![
+            AcceptedAssetMsg {
+                token: Token::Native { denom: "uusdc".into() },
+                rate: Decimal::percent(50),
+            },
+            AcceptedAssetMsg {
+                token: Token::CW20 { addr: Addr::unchecked("cw20") },
+                rate: Decimal::percent(200),
+            },
+        ];
+
+        // repeated tokens could never be contributed
+        for token in [
+            Token::Native { denom: DENOM.into() },
+            Token::CW20 { addr: Addr::unchecked("cw20") },
+        ] {
+            let mut duplicate = project.clone();
+            duplicate.accepted_assets.push(AcceptedAssetMsg {
+                token,
+                rate: Decimal::one(),
+            });
+            let err = instantiate_project(mock_dependencies().as_mut(), duplicate).unwrap_err();
+            assert_eq!(err, ContractError::DuplicateAsset {});
+        }
+        let mut invalid = project.clone();
+        invalid.accepted_assets[1].token = Token::CW20 { addr: Addr::unchecked("CW20") };
+        let err = instantiate_project(mock_dependencies().as_mut(), invalid).unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let info = mock_info("backer", &coins(10, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WrongDenom {
+                expected: "ujuno, uusdc".into()
+            }
+        );
+
+        // 40 + 40uusdc * 0.5 + 20cw20 * 2 = 100
+        let info = mock_info("backer", &coins(40, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer", &coins(40, "uusdc"));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("cw20", &[]);
+        execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer2", 20)).unwrap();
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.current_amount, Uint128::new(100));
+        assert_eq!(project.status, Status::Succeeded);
+        let raised: Vec<u128> = project.assets.iter().map(|a| a.raised.u128()).collect();
+        assert_eq!(raised, vec![40, 40, 20]);
+
+        // owner gets every asset held
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 3);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(40, "uusdc"),
+            })
+        );
+    }
+
+    #[test]
+    fn multiple_assets_refund() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.accepted_assets = vec![AcceptedAssetMsg {
+            token: Token::Native { denom: "uusdc".into() },
+            rate: Decimal::percent(50),
+        }];
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer", &coins(20, "uusdc"));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // unpledge in units of the asset
+        let msg = ExecuteMsg::Unpledge {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(4),
+            asset: Some(1),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("backer", &[]), msg).unwrap();
+        assert_eq!(query_contributed(deps.as_ref(), PROJECT_ID, "backer").amount, Uint128::new(18));
+
+        // failed project returns each asset in its own token
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("backer", &[]), refund).unwrap();
+        let sent: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        assert_eq!(
+            sent,
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "backer".into(),
+                    amount: coins(10, DENOM),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "backer".into(),
+                    amount: coins(16, "uusdc"),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn platform_fee() {
+        let mut deps = mock_dependencies();
+        let platform_fee = |fee| {
+            Some(PlatformFeeMsg {
+                treasury: "treasury".into(),
+                fee,
+            })
+        };
+        let msg = InstantiateMsg {
+            admin: None,
+            project: None,
+            project_owner: None,
+            platform_fee: platform_fee(Decimal::percent(101)),
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidPlatformFee {});
+
+        let msg = InstantiateMsg {
+            admin: None,
+            project: Some(project_msg(Token::CW20 { addr: Addr::unchecked("cw20") })),
+            project_owner: None,
+            platform_fee: platform_fee(Decimal::permille(25)),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let info = mock_info("cw20", &[]);
+        execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 200)).unwrap();
+
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let transfer = |recipient: &str, amount| {
+            Cw20Contract(Addr::unchecked("cw20"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        assert_eq!(res.messages[0].msg, transfer(OWNER, 195));
+        assert_eq!(res.messages[1].msg, transfer("treasury", 5));
+        assert!(res.attributes.iter().any(|a| a.key == "fee" && a.value == "5"));
+
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.platform_fee, Decimal::permille(25));
+        assert_eq!(project.fee_amount, Uint128::new(5));
+    }
+
+    #[test]
+    fn referrals() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.referral_reward = 10;
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let contribute = |referrer: Option<&str>| ExecuteMsg::Contribute {
+            project_id: PROJECT_ID,
+            tier: None,
+            proof: None,
+            referrer: referrer.map(String::from),
+        };
+
+        let info = mock_info("backer", &coins(50, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, contribute(Some("backer"))).unwrap_err();
+        assert_eq!(err, ContractError::InvalidReferrer {});
+
+        let info = mock_info("backer", &coins(50, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some("promoter"))).unwrap();
+        // backers stay with their first referrer
+        let info = mock_info("backer", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some("promoter2"))).unwrap();
+        let info = mock_info("backer2", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some("promoter2"))).unwrap();
+        let info = mock_info("backer3", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(None)).unwrap();
+
+        // a referrer given later takes over what the backer contributed before
+        let info = mock_info("backer4", &coins(50, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(None)).unwrap();
+        let info = mock_info("backer4", &coins(10, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute(Some("promoter3"))).unwrap();
+        let msg = QueryMsg::GetReferral {
+            project_id: PROJECT_ID,
+            referrer: Addr::unchecked("promoter3"),
+        };
+        let res: ReferralResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.amount, Uint128::new(60));
+        let msg = ExecuteMsg::Unpledge {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(60),
+            asset: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("backer4", &[]), msg).unwrap();
+
+        let msg = QueryMsg::ListTopReferrers {
+            project_id: PROJECT_ID,
+            limit: None,
+        };
+        let res: ListReferralsResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let leaderboard: Vec<(&str, u128)> = res
+            .referrals
+            .iter()
+            .map(|r| (r.referrer.as_str(), r.amount.u128()))
+            .collect();
+        assert_eq!(leaderboard, vec![("promoter", 60), ("promoter2", 20), ("promoter3", 0)]);
+
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let sent: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        let send = |recipient: &str, amount| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: coins(amount, DENOM),
+            })
+        };
+        assert_eq!(
+            sent,
+            vec![send(OWNER, 92), send("promoter", 6), send("promoter2", 2)]
+        );
+
+        let msg = QueryMsg::GetReferral {
+            project_id: PROJECT_ID,
+            referrer: Addr::unchecked("promoter"),
+        };
+        let res: ReferralResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.rewarded, Uint128::new(6));
+    }
+
+    #[test]
+    fn matching_pool() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+
+        let deposit = ExecuteMsg::DepositMatchingFunds {
+            project_id: PROJECT_ID,
+            ratio: Decimal::one(),
+        };
+        let info = mock_info("sponsor", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, deposit.clone()).unwrap();
+        let info = mock_info("sponsor2", &coins(30, DENOM));
+        let err = execute(deps.as_mut(), mock_env(), info, deposit).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::MatchingPoolExists {
+                sponsor: Addr::unchecked("sponsor")
+            }
+        );
+
+        let info = mock_info("backer", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        // matching stops once the deposit runs out
+        let info = mock_info("backer2", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let msg = QueryMsg::GetProjectInfo { project_id: PROJECT_ID };
+        let res: GetProjectInfoResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.current_amount, Uint128::new(70));
+
+        // unpledging gives the match back to the pool
+        let msg = ExecuteMsg::Unpledge {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(10),
+            asset: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("backer", &[]), msg).unwrap();
+        let msg = QueryMsg::GetMatchingPool { project_id: PROJECT_ID };
+        let res: MatchingPoolResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.matched, Uint128::new(20));
+        assert_eq!(res.remaining, Uint128::new(10));
+
+        // project failed, sponsor gets everything back
+        let reclaim = ExecuteMsg::ReclaimMatchingFunds { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("sponsor", &[]), reclaim.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::ProjectNotEnded {});
+        let info = mock_info("sponsor", &[]);
+        let res = execute(deps.as_mut(), ended_env(), info, reclaim.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sponsor".into(),
+                amount: coins(30, DENOM),
+            })
+        );
+        let err = execute(deps.as_mut(), ended_env(), mock_info("sponsor", &[]), reclaim)
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToReclaim {});
+
+        // backers get their own contribution back
+        let msg = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("backer2", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "backer2".into(),
+                amount: coins(20, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn matching_pool_without_contributions() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let msg = ExecuteMsg::CreateProject(Box::new(project_msg(Token::Native {
+            denom: DENOM.into(),
+        })));
+        execute(deps.as_mut(), mock_env(), mock_info("owner2", &[]), msg).unwrap();
+        for project_id in [1, 2] {
+            let deposit = ExecuteMsg::DepositMatchingFunds {
+                project_id,
+                ratio: Decimal::one(),
+            };
+            let info = mock_info("sponsor", &coins(30, DENOM));
+            execute(deps.as_mut(), mock_env(), info, deposit).unwrap();
+        }
+        let msg = ExecuteMsg::Cancel { project_id: 2 };
+        execute(deps.as_mut(), mock_env(), mock_info("owner2", &[]), msg).unwrap();
+
+        // failed and cancelled projects without backers give the whole deposit back
+        let refund = CosmosMsg::Bank(BankMsg::Send {
+            to_address: "sponsor".into(),
+            amount: coins(30, DENOM),
+        });
+        let reclaim = ExecuteMsg::ReclaimMatchingFunds { project_id: 2 };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("sponsor", &[]), reclaim).unwrap();
+        assert_eq!(res.messages[0].msg, refund);
+        let reclaim = ExecuteMsg::ReclaimMatchingFunds { project_id: 1 };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("sponsor", &[]), reclaim).unwrap();
+        assert_eq!(res.messages[0].msg, refund);
+    }
+
+    #[test]
+    fn quadratic_funding_round() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let msg = ExecuteMsg::CreateProject(Box::new(project_msg(Token::Native {
+            denom: DENOM.into(),
+        })));
+        execute(deps.as_mut(), mock_env(), mock_info("owner2", &[]), msg).unwrap();
+
+        for backer in ["alice", "bob", "dave"] {
+            let info = mock_info(backer, &coins(25, DENOM));
+            execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        }
+        let info = mock_info("carol", &coins(16, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let msg = ExecuteMsg::Contribute {
+            project_id: 2,
+            tier: None,
+            proof: None,
+            referrer: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("erin", &coins(100, DENOM)), msg).unwrap();
+
+        let end_time = ended_env().block.time.seconds();
+        let msg = ExecuteMsg::CreateRound {
+            token: Token::Native { denom: DENOM.into() },
+            project_ids: vec![1, 2],
+            attestor: Some("attestor".into()),
+            end_time,
+        };
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::FundRound { round_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("funder", &coins(50, DENOM)), msg).unwrap();
+
+        // dave is left out, it didn't pass the sybil check
+        let msg = ExecuteMsg::AttestContributors {
+            round_id: 1,
+            addresses: vec!["alice".into(), "bob".into(), "carol".into(), "erin".into()],
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("attestor", &[]), msg).unwrap();
+        // contributions after the attestation count too
+        let info = mock_info("carol", &coins(9, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // (5 + 5 + 5)^2 = 225 against 10^2 = 100
+        let expected = vec![
+            ProjectMatch {
+                project_id: 1,
+                amount: Uint128::new(34),
+            },
+            ProjectMatch {
+                project_id: 2,
+                amount: Uint128::new(15),
+            },
+        ];
+        let msg = QueryMsg::EstimateRoundMatches { round_id: 1 };
+        let res: RoundMatchesResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.matches, expected);
+
+        let msg = ExecuteMsg::DistributeRound { round_id: 1 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotTallied {});
+        let msg = ExecuteMsg::TallyRound { round_id: 1 };
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::RoundNotEnded { end_time });
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        // payouts wait until the matches are in
+        let withdraw = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::RoundPending { round_id: 1 });
+
+        // matches become project funds, only the rounding dust goes back to admin
+        let msg = ExecuteMsg::DistributeRound { round_id: 1 };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let send = |recipient: &str, amount| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: coins(amount, DENOM),
+            })
+        };
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, send(OWNER, 1));
+        assert_eq!(query_project(deps.as_ref(), 1).current_amount, Uint128::new(134));
+        assert_eq!(query_project(deps.as_ref(), 2).current_amount, Uint128::new(115));
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw).unwrap();
+        assert_eq!(res.messages[0].msg, send(OWNER, 134));
+
+        // a project that paid out can't take a match anymore
+        let msg = ExecuteMsg::CreateRound {
+            token: Token::Native { denom: DENOM.into() },
+            project_ids: vec![1, 2],
+            attestor: None,
+            end_time: end_time + 100,
+        };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundProjectWithdrawn { project_id: 1 });
+
+        // backers of an abandoned project get their own part back, the match goes to admin
+        let msg = ExecuteMsg::Abandon { project_id: 2 };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("owner2", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, send(OWNER, 15));
+        let msg = ExecuteMsg::Refund {
+            project_id: 2,
+            receipt: None,
+        };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("erin", &[]), msg).unwrap();
+        assert_eq!(res.messages[0].msg, send("erin", 100));
+
+        let msg = QueryMsg::GetRound { round_id: 1 };
+        let res: RoundResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.status, RoundStatus::Distributed);
+        assert_eq!(res.matches, expected);
+
+        assert_eq!(isqrt(24), 4);
+        assert_eq!(isqrt(25), 5);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn cancelled_round() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.end_time = ended_env().block.time.seconds() + 1000;
+        let msg = ExecuteMsg::CreateProject(Box::new(project));
+        execute(deps.as_mut(), mock_env(), mock_info("owner2", &[]), msg).unwrap();
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        // the round can't outlast its projects
+        let create = |project_ids: Vec<u64>| ExecuteMsg::CreateRound {
+            token: Token::Native { denom: DENOM.into() },
+            project_ids,
+            attestor: None,
+            end_time: ended_env().block.time.seconds(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), create(vec![1, 2]))
+            .unwrap_err();
+        assert_eq!(err, ContractError::RoundProjectEndsLate { project_id: 2 });
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), create(vec![1])).unwrap();
+        for funder in ["funder", "funder2"] {
+            let msg = ExecuteMsg::FundRound { round_id: 1 };
+            let info = mock_info(funder, &coins(30, DENOM));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+        let msg = ExecuteMsg::FundRound { round_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("funder", &coins(20, DENOM)), msg).unwrap();
+
+        let withdraw = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::RoundPending { round_id: 1 });
+        let reclaim = ExecuteMsg::ReclaimRoundFunds { round_id: 1 };
+        let err = execute(deps.as_mut(), ended_env(), mock_info("funder", &[]), reclaim.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::RoundNotCancelled {});
+
+        let cancel = ExecuteMsg::CancelRound { round_id: 1 };
+        let err = execute(deps.as_mut(), ended_env(), mock_info("funder", &[]), cancel.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), cancel.clone()).unwrap();
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), cancel).unwrap_err();
+        assert_eq!(err, ContractError::RoundClosed {});
+
+        // project pays out on its own, funders take the pool back
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), withdraw).unwrap();
+        let send = |recipient: &str, amount| {
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: coins(amount, DENOM),
+            })
+        };
+        for (funder, amount) in [("funder", 50), ("funder2", 30)] {
+            let info = mock_info(funder, &[]);
+            let res = execute(deps.as_mut(), ended_env(), info, reclaim.clone()).unwrap();
+            assert_eq!(res.messages[0].msg, send(funder, amount));
+        }
+        let err = execute(deps.as_mut(), ended_env(), mock_info("funder", &[]), reclaim)
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToReclaim {});
+    }
+
+    #[test]
+    fn vested_withdrawal() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::CW20 { addr: Addr::unchecked("cw20") });
+        project.vesting = Some(VestingMsg {
+            cliff: 1000,
+            duration: 100,
+        });
+        let err = instantiate_project(deps.as_mut(), project.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InvalidVesting {});
+
+        project.vesting = Some(VestingMsg {
+            cliff: 100,
+            duration: 1000,
+        });
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let info = mock_info("cw20", &[]);
+        execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 200)).unwrap();
+
+        // owner share stays locked at withdrawal
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        assert!(res.messages.is_empty());
+
+        let after = |seconds| {
+            let mut env = ended_env();
+            env.block.time = env.block.time.plus_seconds(seconds);
+            env
+        };
+        let claim = ExecuteMsg::ClaimVested { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), after(50), mock_info(OWNER, &[]), claim.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        let transfer = |amount| {
+            Cw20Contract(Addr::unchecked("cw20"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: OWNER.into(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        let res = execute(deps.as_mut(), after(500), mock_info(OWNER, &[]), claim.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, transfer(100));
+
+        let msg = QueryMsg::GetVesting { project_id: PROJECT_ID };
+        let res: VestingResponse =
+            from_binary(&query(deps.as_ref(), after(750), msg).unwrap()).unwrap();
+        assert_eq!(res.claimed, Uint128::new(100));
+        assert_eq!(res.claimable, Uint128::new(50));
+
+        let info = mock_info(OWNER, &[]);
+        let res = execute(deps.as_mut(), after(2000), info, claim.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, transfer(100));
+        let err = execute(deps.as_mut(), after(3000), mock_info(OWNER, &[]), claim).unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+    }
+
+    #[test]
+    fn governance_proposals() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.governance = Some(GovernanceMsg {
+            quorum: Decimal::percent(50),
+            threshold: Decimal::percent(60),
+            voting_period: 100,
+        });
+        instantiate_project(deps.as_mut(), project).unwrap();
+        for (backer, amount) in [("alice", 60), ("bob", 40)] {
+            let info = mock_info(backer, &coins(amount, DENOM));
+            execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        }
+
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::GovernanceConfigured {});
+
+        let propose = |amount| ExecuteMsg::CreateProposal {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(amount),
+            purpose: "tooling".into(),
+        };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), propose(70)).unwrap();
+        let err =
+            execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), propose(40)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientFunds {
+                available: Uint128::new(30)
+            }
+        );
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), propose(30)).unwrap();
+
+        let vote = |proposal_id, approve| ExecuteMsg::Vote {
+            project_id: PROJECT_ID,
+            proposal_id,
+            approve,
+            receipts: vec![],
+        };
+        let err =
+            execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), vote(1, true)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), ended_env(), mock_info("alice", &[]), vote(1, true)).unwrap();
+        execute(deps.as_mut(), ended_env(), mock_info("bob", &[]), vote(1, false)).unwrap();
+        let err =
+            execute(deps.as_mut(), ended_env(), mock_info("bob", &[]), vote(1, true)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+        // 40 of 100 misses the quorum
+        execute(deps.as_mut(), ended_env(), mock_info("bob", &[]), vote(2, true)).unwrap();
+
+        let execute_proposal = |proposal_id| ExecuteMsg::ExecuteProposal {
+            project_id: PROJECT_ID,
+            proposal_id,
+        };
+        let err = execute(deps.as_mut(), ended_env(), mock_info("bob", &[]), execute_proposal(1))
+            .unwrap_err();
+        let end_time = ended_env().block.time.seconds() + 100;
+        assert_eq!(err, ContractError::VotingNotEnded { end_time });
+
+        let mut env = ended_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), execute_proposal(1)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(70, DENOM),
+            })
+        );
+        let res = execute(deps.as_mut(), env.clone(), info, execute_proposal(2)).unwrap();
+        assert!(res.messages.is_empty());
+
+        let msg = QueryMsg::ListProposals {
+            project_id: PROJECT_ID,
+            start_after: None,
+            limit: None,
+        };
+        let res: ListProposalsResponse =
+            from_binary(&query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        let statuses: Vec<ProposalStatus> = res.proposals.into_iter().map(|p| p.status).collect();
+        assert_eq!(statuses, vec![ProposalStatus::Executed, ProposalStatus::Rejected]);
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.withdrawn_amount, Uint128::new(70));
+    }
+
+    #[test]
+    fn governance_quorum_excludes_matched_funds() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.governance = Some(GovernanceMsg {
+            quorum: Decimal::percent(50),
+            threshold: Decimal::percent(60),
+            voting_period: 100,
+        });
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let deposit = ExecuteMsg::DepositMatchingFunds {
+            project_id: PROJECT_ID,
+            ratio: Decimal::one(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("sponsor", &coins(50, DENOM)), deposit)
+            .unwrap();
+        for (backer, amount) in [("alice", 30), ("bob", 20)] {
+            let info = mock_info(backer, &coins(amount, DENOM));
+            execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        }
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).current_amount, Uint128::new(100));
+
+        let propose = ExecuteMsg::CreateProposal {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(80),
+            purpose: "tooling".into(),
+        };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), propose).unwrap();
+        let vote = ExecuteMsg::Vote {
+            project_id: PROJECT_ID,
+            proposal_id: 1,
+            approve: true,
+            receipts: vec![],
+        };
+        let err = execute(deps.as_mut(), ended_env(), mock_info("sponsor", &[]), vote.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::NoContribution {});
+        execute(deps.as_mut(), ended_env(), mock_info("alice", &[]), vote).unwrap();
+
+        // 30 of the 50 backers put in meets the quorum, the matched 50 has no voter
+        let mut env = ended_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = ExecuteMsg::ExecuteProposal {
+            project_id: PROJECT_ID,
+            proposal_id: 1,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(80, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn governance_receipt_votes() {
+        let mut deps = mock_dependencies();
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.receipt_nft = Some("receipts".into());
+        project.governance = Some(GovernanceMsg {
+            quorum: Decimal::percent(50),
+            threshold: Decimal::percent(60),
+            voting_period: 100,
+        });
+        instantiate_project(deps.as_mut(), project).unwrap();
+        for amount in [60, 40] {
+            let info = mock_info("backer", &coins(amount, DENOM));
+            execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        }
+        // receipt 1-1 got sold to buyer, backer kept 1-2
+        deps.querier.update_wasm(|query| {
+            let token_id = match query {
+                WasmQuery::Smart { msg, .. } => match from_binary(msg).unwrap() {
+                    Cw721QueryMsg::OwnerOf { token_id, .. } => token_id,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            };
+            let owner = if token_id == "1-1" { "buyer" } else { "backer" };
+            let res = OwnerOfResponse {
+                owner: owner.into(),
+                approvals: vec![],
+            };
+            SystemResult::Ok(ContractResult::Ok(to_binary(&res).unwrap()))
+        });
+
+        let propose = ExecuteMsg::CreateProposal {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(70),
+            purpose: "tooling".into(),
+        };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), propose).unwrap();
+        let vote = |receipts: &[&str], approve| ExecuteMsg::Vote {
+            project_id: PROJECT_ID,
+            proposal_id: 1,
+            approve,
+            receipts: receipts.iter().map(|r| r.to_string()).collect(),
+        };
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, vote(&[], true)).unwrap_err();
+        assert_eq!(err, ContractError::ReceiptRequired {});
+        let info = mock_info("backer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, vote(&["1-1"], false)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, vote(&["1-3"], true)).unwrap_err();
+        assert_eq!(err, ContractError::ReceiptNotFound { token_id: "1-3".into() });
+
+        let info = mock_info("buyer", &[]);
+        execute(deps.as_mut(), ended_env(), info, vote(&["1-1"], true)).unwrap();
+        let info = mock_info("buyer", &[]);
+        let err = execute(deps.as_mut(), ended_env(), info, vote(&["1-1"], true)).unwrap_err();
+        assert_eq!(err, ContractError::AlreadyVoted {});
+        let info = mock_info("backer", &[]);
+        execute(deps.as_mut(), ended_env(), info, vote(&["1-2"], false)).unwrap();
+
+        // buyer's 60 carries the vote over backer's 40
+        let mut env = ended_env();
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = ExecuteMsg::ExecuteProposal {
+            project_id: PROJECT_ID,
+            proposal_id: 1,
+        };
+        let res = execute(deps.as_mut(), env, mock_info("buyer", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: OWNER.into(),
+                amount: coins(70, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn ownership_transfer() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        let info = mock_info("backer", &coins(100, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+
+        let expires = mock_env().block.time.seconds() + 50;
+        let propose = ExecuteMsg::ProposeNewOwner {
+            project_id: PROJECT_ID,
+            addr: "newowner".into(),
+            expires: Some(expires),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), propose.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), propose.clone()).unwrap();
+
+        let accept = ExecuteMsg::AcceptOwnership { project_id: PROJECT_ID };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("backer", &[]), accept.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), ended_env(), mock_info("newowner", &[]), accept.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::OwnershipProposalExpired { expires });
+
+        let cancel = ExecuteMsg::CancelOwnershipProposal { project_id: PROJECT_ID };
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), cancel).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), accept.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::NoOwnershipProposal {});
+
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), propose).unwrap();
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.pending_owner.unwrap().owner, Addr::unchecked("newowner"));
+        execute(deps.as_mut(), mock_env(), mock_info("newowner", &[]), accept).unwrap();
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.project_owner, Addr::unchecked("newowner"));
+        assert_eq!(project.pending_owner, None);
+
+        // owner checks follow the new owner
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let err =
+            execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), ended_env(), mock_info("newowner", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "newowner".into(),
+                amount: coins(100, DENOM),
+            })
+        );
+    }
+
+    #[test]
+    fn beneficiaries() {
+        let mut deps = mock_dependencies();
+        let shares = |shares: &[(&str, u64)]| -> Vec<BeneficiaryMsg> {
+            shares
+                .iter()
+                .map(|(address, percentage)| BeneficiaryMsg {
+                    address: address.to_string(),
+                    percentage: *percentage,
+                })
+                .collect()
+        };
+        let mut project = project_msg(Token::CW20 { addr: Addr::unchecked("cw20") });
+        project.beneficiaries = shares(&[("alice", 50), ("bob", 40)]);
+        let err = instantiate_project(deps.as_mut(), project.clone()).unwrap_err();
+        assert_eq!(err, ContractError::InvalidBeneficiaries {});
+
+        project.beneficiaries = shares(&[("alice", 50), ("bob", 50)]);
+        instantiate_project(deps.as_mut(), project).unwrap();
+        let info = mock_info("cw20", &[]);
+        execute(deps.as_mut(), mock_env(), info, cw20_contribution("backer", 101)).unwrap();
+
+        let update = ExecuteMsg::UpdateBeneficiaries {
+            project_id: PROJECT_ID,
+            beneficiaries: shares(&[("alice", 33), ("bob", 33), ("carol", 34)]),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), update.clone())
+            .unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(OWNER, &[]), update.clone()).unwrap();
+        let err =
+            execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), update).unwrap_err();
+        assert_eq!(err, ContractError::ProjectEnded {});
+
+        // last beneficiary takes the rounding remainder
+        let msg = ExecuteMsg::Withdraw { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        let sent: Vec<CosmosMsg> = res.messages.into_iter().map(|m| m.msg).collect();
+        let transfer = |recipient: &str, amount| {
+            Cw20Contract(Addr::unchecked("cw20"))
+                .call(Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: Uint128::new(amount),
+                })
+                .unwrap()
+        };
+        assert_eq!(
+            sent,
+            vec![transfer("alice", 33), transfer("bob", 33), transfer("carol", 35)]
+        );
+    }
+
+    #[test]
+    fn list_contributions() {
+        let mut deps = mock_dependencies();
+        instantiate_with(deps.as_mut(), Token::Native { denom: DENOM.into() });
+        for (backer, amount) in [("carol", 30), ("alice", 10), ("bob", 20), ("alice", 5)] {
+            let info = mock_info(backer, &coins(amount, DENOM));
+            execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        }
+
+        let count = |deps: Deps| {
+            let msg = QueryMsg::GetContributorCount { project_id: PROJECT_ID };
+            let res: ContributorCountResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.count
+        };
+        assert_eq!(count(deps.as_ref()), 3);
+
+        let list = |deps: Deps, start_after: Option<&str>| {
+            let msg = QueryMsg::ListContributions {
+                project_id: PROJECT_ID,
+                start_after: start_after.map(Addr::unchecked),
+                limit: Some(2),
+            };
+            let res: ListContributionsResponse =
+                from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            res.contributions
+                .into_iter()
+                .map(|c| (c.address.to_string(), c.amount.u128()))
+                .collect::<Vec<_>>()
+        };
+        let page = list(deps.as_ref(), None);
+        assert_eq!(page, vec![("alice".to_string(), 15), ("bob".to_string(), 20)]);
+        let page = list(deps.as_ref(), Some("bob"));
+        assert_eq!(page, vec![("carol".to_string(), 30)]);
+
+        // backers taking everything back are no longer counted
+        let msg = ExecuteMsg::Unpledge {
+            project_id: PROJECT_ID,
+            amount: Uint128::new(20),
+            asset: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg).unwrap();
+        assert_eq!(count(deps.as_ref()), 2);
+        assert_eq!(list(deps.as_ref(), Some("alice")), vec![("carol".to_string(), 30)]);
+    }
+
+    #[test]
+    fn matched_milestone_project_abandoned() {
+        let mut deps = mock_dependencies();
+        let end_time = mock_env().block.time.seconds() + 100;
+        let mut project = project_msg(Token::Native { denom: DENOM.into() });
+        project.milestones = vec![
+            MilestoneMsg {
+                title: "prototype".into(),
+                percentage: 40,
+                unlock_time: end_time,
+            },
+            MilestoneMsg {
+                title: "release".into(),
+                percentage: 60,
+                unlock_time: end_time,
+            },
+        ];
+        instantiate_project(deps.as_mut(), project).unwrap();
+
+        let deposit = ExecuteMsg::DepositMatchingFunds {
+            project_id: PROJECT_ID,
+            ratio: Decimal::one(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("sponsor", &coins(50, DENOM)), deposit)
+            .unwrap();
+        let info = mock_info("backer", &coins(30, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        let info = mock_info("backer2", &coins(20, DENOM));
+        execute(deps.as_mut(), mock_env(), info, contribute_msg()).unwrap();
+        assert_eq!(query_project(deps.as_ref(), PROJECT_ID).current_amount, Uint128::new(100));
+
+        let msg = ExecuteMsg::ReleaseMilestone {
+            project_id: PROJECT_ID,
+            index: 0,
+        };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+        // partly released projects can't take a round match
+        let msg = ExecuteMsg::CreateRound {
+            token: Token::Native { denom: DENOM.into() },
+            project_ids: vec![PROJECT_ID],
+            attestor: None,
+            end_time,
+        };
+        let err = execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::RoundProjectWithdrawn { project_id: PROJECT_ID });
+        let msg = ExecuteMsg::Abandon { project_id: PROJECT_ID };
+        execute(deps.as_mut(), ended_env(), mock_info(OWNER, &[]), msg).unwrap();
+
+        // 40 released, backers and sponsor share the other 60 pro rata
+        let refund = ExecuteMsg::Refund {
+            project_id: PROJECT_ID,
+            receipt: None,
+        };
+        for (backer, amount) in [("backer", 18), ("backer2", 12)] {
+            let info = mock_info(backer, &[]);
+            let res = execute(deps.as_mut(), ended_env(), info, refund.clone()).unwrap();
+            assert_eq!(
+                res.messages[0].msg,
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: backer.into(),
+                    amount: coins(amount, DENOM),
+                })
+            );
+        }
+        let err = execute(deps.as_mut(), ended_env(), mock_info("backer", &[]), refund)
+            .unwrap_err();
+        assert_eq!(err, ContractError::AlreadyRefunded {});
+
+        let reclaim = ExecuteMsg::ReclaimMatchingFunds { project_id: PROJECT_ID };
+        let res = execute(deps.as_mut(), ended_env(), mock_info("sponsor", &[]), reclaim)
+            .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "sponsor".into(),
+                amount: coins(30, DENOM),
+            })
+        );
+        let project = query_project(deps.as_ref(), PROJECT_ID);
+        assert_eq!(project.withdrawn_amount, Uint128::new(40));
+        assert_eq!(project.refunded_amount, Uint128::new(30));
+    }
 }
@@ -1,7 +1,7 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{Addr, StdError, Uint128};
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
@@ -9,8 +9,255 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Custom Error val: {val:?}")]
-    CustomError { val: String },
-    // Add any other custom errors you like here.
-    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+    #[error("Project {project_id} not found")]
+    ProjectNotFound { project_id: u64 },
+
+    #[error("No ownership transfer proposed")]
+    NoOwnershipProposal {},
+
+    #[error("Ownership proposal expired at {expires}")]
+    OwnershipProposalExpired { expires: u64 },
+
+    #[error("Ownership proposal must expire in the future")]
+    InvalidExpiry {},
+
+    #[error("Project owner cannot contribute")]
+    OwnerCannotContribute {},
+
+    #[error("Project starts at {start_time}")]
+    ProjectNotStarted { start_time: u64 },
+
+    #[error("Project already ended")]
+    ProjectEnded {},
+
+    #[error("Project cancelled")]
+    ProjectCancelled {},
+
+    #[error("Address is not on the allowlist")]
+    NotAllowlisted {},
+
+    #[error("Merkle root and proof must be hex encoded sha256 hashes")]
+    InvalidMerkleHash {},
+
+    #[error("Project reached its hard cap")]
+    HardCapReached {},
+
+    #[error("Contribution is too small to be credited")]
+    ZeroContribution {},
+
+    #[error("Amount must not be zero")]
+    ZeroAmount {},
+
+    #[error("Start time must be before end time")]
+    InvalidStartTime {},
+
+    #[error("Hard cap must not be below target amount")]
+    InvalidMaxAmount {},
+
+    #[error("Contributions must add up to at least {min_contribution}")]
+    BelowMinContribution { min_contribution: Uint128 },
+
+    #[error("Contribution exceeds per address maximum, {remaining} left")]
+    AboveMaxContribution { remaining: Uint128 },
+
+    #[error("Minimum contribution must not be above maximum contribution")]
+    InvalidContributionLimits {},
+
+    #[error("Project not ended")]
+    ProjectNotEnded {},
+
+    #[error("Project not succeeded")]
+    NotSucceeded {},
+
+    #[error("Project not failed")]
+    NotFailed {},
+
+    #[error("Project accepts native token only")]
+    NativeOnly {},
+
+    #[error("Project accepts cw20 token only")]
+    Cw20Only {},
+
+    #[error("Platform fee must not be above 1")]
+    InvalidPlatformFee {},
+
+    #[error("Conversion rate of accepted assets must not be zero")]
+    InvalidConversionRate {},
+
+    #[error("Accepted assets must differ from the main token and from each other")]
+    DuplicateAsset {},
+
+    #[error("Project with receipt nft accepts its main token only")]
+    ReceiptsSingleAsset {},
+
+    #[error("Asset {index} not accepted")]
+    AssetNotFound { index: u32 },
+
+    #[error("Only denom {expected} accepted")]
+    WrongDenom { expected: String },
+
+    #[error("Send exactly one coin")]
+    MultipleDenoms {},
+
+    #[error("Only cw20 token {expected} accepted")]
+    WrongCw20Token { expected: Addr },
+
+    #[error("Milestones must add up to 100 percent")]
+    InvalidMilestones {},
+
+    #[error("Project releases funds by milestone")]
+    MilestonesConfigured {},
+
+    #[error("Vesting cliff must not be after the end of its duration")]
+    InvalidVesting {},
+
+    #[error("Nothing unlocked to claim")]
+    NothingToClaim {},
+
+    #[error("Quorum and threshold must be between 0 and 1, voting period must not be zero")]
+    InvalidGovernance {},
+
+    #[error("Governance cannot be combined with milestones or vesting")]
+    GovernanceExclusive {},
+
+    #[error("Project releases funds by proposal")]
+    GovernanceConfigured {},
+
+    #[error("Project has no governance")]
+    NoGovernance {},
+
+    #[error("Only {available} left to propose")]
+    InsufficientFunds { available: Uint128 },
+
+    #[error("Proposal {proposal_id} not found")]
+    ProposalNotFound { proposal_id: u64 },
+
+    #[error("Proposal is not open")]
+    ProposalNotOpen {},
+
+    #[error("Voting ended")]
+    VotingEnded {},
+
+    #[error("Voting ends at {end_time}")]
+    VotingNotEnded { end_time: u64 },
+
+    #[error("Already voted")]
+    AlreadyVoted {},
+
+    #[error("Beneficiaries must add up to 100 percent")]
+    InvalidBeneficiaries {},
+
+    #[error("Milestone {index} not found")]
+    MilestoneNotFound { index: u32 },
+
+    #[error("Milestone locked until {unlock_time}")]
+    MilestoneLocked { unlock_time: u64 },
+
+    #[error("Milestone {index} already released")]
+    MilestoneAlreadyReleased { index: u32 },
+
+    #[error("Reward tier {index} not found")]
+    TierNotFound { index: u32 },
+
+    #[error("Reward tier {index} needs at least {min_amount} contributed")]
+    BelowTierMinimum { index: u32, min_amount: Uint128 },
+
+    #[error("Reward tier {index} sold out")]
+    TierSoldOut { index: u32 },
+
+    #[error("Project refunds and votes by receipt, receipt token id required")]
+    ReceiptRequired {},
+
+    #[error("Receipt {token_id} not found")]
+    ReceiptNotFound { token_id: String },
+
+    #[error("Raised funds already withdrawn")]
+    AlreadyWithdrawn {},
+
+    #[error("Contribution already refunded")]
+    AlreadyRefunded {},
+
+    #[error("No contribution found")]
+    NoContribution {},
+
+    #[error("Cannot reassign legacy contribution to {recipient}")]
+    InvalidReassignRecipient { recipient: Addr },
+
+    #[error("Only {available} contribution left to unpledge")]
+    InsufficientContribution { available: Uint128 },
+
+    #[error("Contributions held by receipts cannot be unpledged")]
+    UnpledgeWithReceipts {},
+
+    #[error("Unpledge penalty must be a percentage")]
+    InvalidUnpledgePenalty {},
+
+    #[error("Referral reward must be a percentage")]
+    InvalidReferralReward {},
+
+    #[error("Contributors and project owner cannot refer themselves")]
+    InvalidReferrer {},
+
+    #[error("Project already has matching funds of {sponsor}")]
+    MatchingPoolExists { sponsor: Addr },
+
+    #[error("Matching ratio must not be zero")]
+    InvalidMatchingRatio {},
+
+    #[error("No matching funds to reclaim")]
+    NothingToReclaim {},
+
+    #[error("Round {round_id} not found")]
+    RoundNotFound { round_id: u64 },
+
+    #[error("Round is not open")]
+    RoundNotOpen {},
+
+    #[error("Round is not tallied")]
+    RoundNotTallied {},
+
+    #[error("Project {project_id} does not take the round token")]
+    RoundTokenMismatch { project_id: u64 },
+
+    #[error("Project {project_id} already paid out funds")]
+    RoundProjectWithdrawn { project_id: u64 },
+
+    #[error("Project funds wait for the matches of round {round_id}")]
+    RoundPending { round_id: u64 },
+
+    #[error("Project {project_id} ends after the round")]
+    RoundProjectEndsLate { project_id: u64 },
+
+    #[error("Round ends at {end_time}")]
+    RoundNotEnded { end_time: u64 },
+
+    #[error("Round is already distributed or cancelled")]
+    RoundClosed {},
+
+    #[error("Round is not cancelled")]
+    RoundNotCancelled {},
+
+    #[error("Only {available} legacy contribution left to reassign")]
+    InsufficientLegacyContribution { available: Uint128 },
+
+    #[error("Campaign instantiate msg must carry a project")]
+    CampaignProjectMissing {},
+
+    #[error("Campaign {address} not found")]
+    CampaignNotFound { address: Addr },
+
+    #[error("Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("Cannot migrate from contract {contract}")]
+    CannotMigrate { contract: String },
+
+    #[error("Cannot downgrade from {from} to {to}")]
+    CannotDowngrade { from: String, to: String },
+
+    #[error("Contract holds {balance} but migrated project owes {owed}")]
+    LegacyBalanceShort { owed: Uint128, balance: Uint128 },
+
+    #[error("Invalid contract version {version}")]
+    InvalidVersion { version: String },
 }
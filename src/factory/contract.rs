@@ -0,0 +1,561 @@
+#[cfg(all(feature = "factory", not(feature = "library")))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Reply, Response, StdError,
+    StdResult, SubMsg, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use cw_utils::parse_reply_instantiate_data;
+
+use crate::error::ContractError;
+use crate::factory::msg::{
+    ExecuteMsg, GetConfigResponse, InstantiateMsg, ListCampaignsResponse, QueryMsg,
+};
+use crate::factory::state::{
+    campaigns, status_key, Campaign, Config, PendingCampaign, CONFIG, PENDING_CAMPAIGN,
+};
+use crate::contract::platform_fee_of;
+use crate::msg::{self as campaign_msg, GetProjectInfoResponse, PlatformFeeMsg};
+use crate::state::Status;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crowd-funding-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// project created along with a campaign contract
+const CAMPAIGN_PROJECT_ID: u64 = 1;
+
+const INSTANTIATE_CAMPAIGN_REPLY_ID: u64 = 1;
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[cfg_attr(all(feature = "factory", not(feature = "library")), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender.clone(),
+    };
+    let platform_fee = platform_fee_of(deps.api, msg.platform_fee)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin: admin.clone(),
+            code_id: msg.code_id,
+            platform_fee,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("admin", admin)
+        .add_attribute("code_id", msg.code_id.to_string()))
+}
+
+#[cfg_attr(all(feature = "factory", not(feature = "library")), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateCampaign {
+            instantiate_msg,
+            label,
+        } => try_create_campaign(deps, info, *instantiate_msg, label),
+        ExecuteMsg::UpdateCodeId { code_id } => try_update_code_id(deps, info, code_id),
+        ExecuteMsg::UpdatePlatformFee { platform_fee } => {
+            try_update_platform_fee(deps, info, platform_fee)
+        }
+        ExecuteMsg::SyncCampaign { address } => try_sync_campaign(deps, address),
+    }
+}
+
+pub fn try_create_campaign(
+    deps: DepsMut,
+    info: MessageInfo,
+    mut instantiate_msg: campaign_msg::InstantiateMsg,
+    label: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let title = match &instantiate_msg.project {
+        Some(project) => project.title.clone(),
+        None => return Err(ContractError::CampaignProjectMissing {}),
+    };
+
+    // campaign project always belongs to whoever asked the factory for it,
+    // is administered by the factory admin and pays the platform fee of the factory
+    instantiate_msg.admin = Some(config.admin.to_string());
+    instantiate_msg.project_owner = Some(info.sender.to_string());
+    instantiate_msg.platform_fee = config.platform_fee.map(|platform_fee| PlatformFeeMsg {
+        treasury: platform_fee.treasury.into(),
+        fee: platform_fee.fee,
+    });
+    PENDING_CAMPAIGN.save(
+        deps.storage,
+        &PendingCampaign {
+            owner: info.sender.clone(),
+            title,
+        },
+    )?;
+
+    let msg = WasmMsg::Instantiate {
+        admin: Some(config.admin.into()),
+        code_id: config.code_id,
+        msg: to_binary(&instantiate_msg)?,
+        funds: vec![],
+        label,
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(msg, INSTANTIATE_CAMPAIGN_REPLY_ID))
+        .add_attribute("action", "create_campaign")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn try_update_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    code_id: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.code_id = code_id;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_code_id")
+        .add_attribute("code_id", code_id.to_string()))
+}
+
+pub fn try_update_platform_fee(
+    deps: DepsMut,
+    info: MessageInfo,
+    platform_fee: Option<PlatformFeeMsg>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.platform_fee = platform_fee_of(deps.api, platform_fee)?;
+    CONFIG.save(deps.storage, &config)?;
+
+    let fee = config.platform_fee.map(|platform_fee| platform_fee.fee).unwrap_or_default();
+    Ok(Response::new()
+        .add_attribute("action", "update_platform_fee")
+        .add_attribute("fee", fee.to_string()))
+}
+
+pub fn try_sync_campaign(deps: DepsMut, address: String) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+    let campaign = campaigns()
+        .may_load(deps.storage, &address)?
+        .ok_or_else(|| ContractError::CampaignNotFound {
+            address: address.clone(),
+        })?;
+
+    // project info query reports the effective status, e.g. Failed after end_time,
+    // and the current owner once ownership changed hands
+    let project: GetProjectInfoResponse = deps.querier.query_wasm_smart(
+        &address,
+        &campaign_msg::QueryMsg::GetProjectInfo {
+            project_id: CAMPAIGN_PROJECT_ID,
+        },
+    )?;
+
+    let updated = Campaign {
+        owner: project.project_owner,
+        status: project.status,
+        ..campaign.clone()
+    };
+    campaigns().replace(deps.storage, &address, Some(&updated), Some(&campaign))?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync_campaign")
+        .add_attribute("campaign", address)
+        .add_attribute("status", status_key(&updated.status)))
+}
+
+#[cfg_attr(all(feature = "factory", not(feature = "library")), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != INSTANTIATE_CAMPAIGN_REPLY_ID {
+        return Err(ContractError::UnknownReplyId { id: msg.id });
+    }
+
+    let res = parse_reply_instantiate_data(msg)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let address = deps.api.addr_validate(&res.contract_address)?;
+
+    let pending = PENDING_CAMPAIGN.load(deps.storage)?;
+    PENDING_CAMPAIGN.remove(deps.storage);
+
+    // campaign may be announced ahead of its start time
+    let project: GetProjectInfoResponse = deps.querier.query_wasm_smart(
+        &address,
+        &campaign_msg::QueryMsg::GetProjectInfo {
+            project_id: CAMPAIGN_PROJECT_ID,
+        },
+    )?;
+
+    let campaign = Campaign {
+        address: address.clone(),
+        owner: pending.owner,
+        title: pending.title,
+        status: project.status,
+    };
+    campaigns().save(deps.storage, &address, &campaign)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "register_campaign")
+        .add_attribute("campaign", address))
+}
+
+#[cfg_attr(all(feature = "factory", not(feature = "library")), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetCampaign { address } => to_binary(&campaigns().load(deps.storage, &address)?),
+        QueryMsg::ListCampaigns { start_after, limit } => {
+            to_binary(&query_list_campaigns(deps, start_after, limit)?)
+        }
+        QueryMsg::ListCampaignsByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_campaigns_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::ListCampaignsByStatus {
+            status,
+            start_after,
+            limit,
+        } => to_binary(&query_campaigns_by_status(deps, status, start_after, limit)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<GetConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(GetConfigResponse {
+        admin: config.admin,
+        code_id: config.code_id,
+        platform_fee: config.platform_fee,
+    })
+}
+
+fn query_list_campaigns(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<ListCampaignsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let campaigns = campaigns()
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, campaign)| campaign))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListCampaignsResponse { campaigns })
+}
+
+fn query_campaigns_by_owner(
+    deps: Deps,
+    owner: Addr,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<ListCampaignsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let campaigns = campaigns()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, campaign)| campaign))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListCampaignsResponse { campaigns })
+}
+
+fn query_campaigns_by_status(
+    deps: Deps,
+    status: Status,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<ListCampaignsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+
+    let campaigns = campaigns()
+        .idx
+        .status
+        .prefix(status_key(&status))
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, campaign)| campaign))
+        .collect::<StdResult<_>>()?;
+
+    Ok(ListCampaignsResponse { campaigns })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::{coins, Decimal, Empty, Uint128};
+    use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+    use crate::msg::{ProjectMsg, Token};
+
+    const ADMIN: &str = "admin";
+    const OWNER: &str = "owner";
+
+    fn campaign_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            crate::contract::execute,
+            crate::contract::instantiate,
+            crate::contract::query,
+        );
+        Box::new(contract)
+    }
+
+    fn factory_contract() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+        Box::new(contract)
+    }
+
+    fn instantiate_factory(
+        app: &mut App,
+        campaign_code_id: u64,
+        factory_code_id: u64,
+        platform_fee: Option<PlatformFeeMsg>,
+    ) -> Addr {
+        let msg = InstantiateMsg {
+            code_id: campaign_code_id,
+            admin: None,
+            platform_fee,
+        };
+        let admin = Addr::unchecked(ADMIN);
+        app.instantiate_contract(factory_code_id, admin, &msg, &[], "factory", None)
+            .unwrap()
+    }
+
+    fn campaign_instantiate_msg(end_time: u64) -> campaign_msg::InstantiateMsg {
+        campaign_msg::InstantiateMsg {
+            project: Some(ProjectMsg {
+                title: "campaign".into(),
+                description: "description".into(),
+                token: Token::Native {
+                    denom: "ujuno".into(),
+                },
+                target_amount: Uint128::new(100),
+                max_amount: None,
+                min_contribution: None,
+                max_contribution_per_address: None,
+                unpledge_penalty: 0,
+                referral_reward: 0,
+                start_time: None,
+                end_time,
+                milestones: vec![],
+                reward_tiers: vec![],
+                merkle_root: None,
+                accepted_assets: vec![],
+                receipt_nft: None,
+                vesting: None,
+                governance: None,
+                beneficiaries: vec![],
+            }),
+            // factory overrides these with its admin and the sender
+            admin: Some("someone_else".into()),
+            project_owner: Some("someone_else".into()),
+            platform_fee: None,
+        }
+    }
+
+    #[test]
+    fn create_and_list_campaigns() {
+        let mut app = App::default();
+        let campaign_code_id = app.store_code(campaign_contract());
+        let factory_code_id = app.store_code(factory_contract());
+
+        let factory = instantiate_factory(&mut app, campaign_code_id, factory_code_id, None);
+
+        let end_time = app.block_info().time.seconds() + 100;
+        let msg = ExecuteMsg::CreateCampaign {
+            instantiate_msg: Box::new(campaign_instantiate_msg(end_time)),
+            label: "campaign".into(),
+        };
+        app.execute_contract(Addr::unchecked(OWNER), factory.clone(), &msg, &[])
+            .unwrap();
+
+        let res: ListCampaignsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &factory,
+                &QueryMsg::ListCampaignsByOwner {
+                    owner: Addr::unchecked(OWNER),
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        assert_eq!(res.campaigns.len(), 1);
+        let campaign = res.campaigns[0].clone();
+        assert_eq!(campaign.title, "campaign");
+        assert_eq!(campaign.status, Status::Ongoing);
+
+        // project in the campaign contract belongs to the factory caller
+        let project: GetProjectInfoResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &campaign.address,
+                &campaign_msg::QueryMsg::GetProjectInfo {
+                    project_id: CAMPAIGN_PROJECT_ID,
+                },
+            )
+            .unwrap();
+        assert_eq!(project.project_owner, Addr::unchecked(OWNER));
+
+        // and is administered by the factory admin, not whoever the caller named
+        let msg = campaign_msg::ExecuteMsg::Cancel {
+            project_id: CAMPAIGN_PROJECT_ID,
+        };
+        let err = app
+            .execute_contract(Addr::unchecked("someone_else"), campaign.address.clone(), &msg, &[])
+            .unwrap_err();
+        assert_eq!(err.downcast::<ContractError>().unwrap(), ContractError::Unauthorized {});
+
+        // after end_time without contributions the campaign is failed
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let msg = ExecuteMsg::SyncCampaign {
+            address: campaign.address.to_string(),
+        };
+        app.execute_contract(Addr::unchecked("anyone"), factory.clone(), &msg, &[])
+            .unwrap();
+
+        let by_status = |status: Status| -> ListCampaignsResponse {
+            app.wrap()
+                .query_wasm_smart(
+                    &factory,
+                    &QueryMsg::ListCampaignsByStatus {
+                        status,
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap()
+        };
+        assert!(by_status(Status::Ongoing).campaigns.is_empty());
+        assert_eq!(by_status(Status::Failed).campaigns.len(), 1);
+
+        let msg = campaign_msg::ExecuteMsg::Cancel {
+            project_id: CAMPAIGN_PROJECT_ID,
+        };
+        app.execute_contract(Addr::unchecked(ADMIN), campaign.address, &msg, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn campaign_needs_project() {
+        let mut app = App::default();
+        let campaign_code_id = app.store_code(campaign_contract());
+        let factory_code_id = app.store_code(factory_contract());
+        let factory = instantiate_factory(&mut app, campaign_code_id, factory_code_id, None);
+
+        let mut instantiate_msg = campaign_instantiate_msg(0);
+        instantiate_msg.project = None;
+        let msg = ExecuteMsg::CreateCampaign {
+            instantiate_msg: Box::new(instantiate_msg),
+            label: "campaign".into(),
+        };
+        let err = app
+            .execute_contract(Addr::unchecked(OWNER), factory, &msg, &[])
+            .unwrap_err();
+        assert_eq!(
+            err.downcast::<ContractError>().unwrap(),
+            ContractError::CampaignProjectMissing {}
+        );
+    }
+
+    #[test]
+    fn campaign_pays_factory_fee() {
+        let mut app = App::new(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked("backer"), coins(100, "ujuno"))
+                .unwrap();
+        });
+        let campaign_code_id = app.store_code(campaign_contract());
+        let factory_code_id = app.store_code(factory_contract());
+        let platform_fee = PlatformFeeMsg {
+            treasury: "treasury".into(),
+            fee: Decimal::percent(10),
+        };
+        let factory = instantiate_factory(
+            &mut app,
+            campaign_code_id,
+            factory_code_id,
+            Some(platform_fee),
+        );
+
+        // caller tries to get away without the platform fee
+        let end_time = app.block_info().time.seconds() + 100;
+        let mut instantiate_msg = campaign_instantiate_msg(end_time);
+        instantiate_msg.platform_fee = Some(PlatformFeeMsg {
+            treasury: OWNER.into(),
+            fee: Decimal::zero(),
+        });
+        let msg = ExecuteMsg::CreateCampaign {
+            instantiate_msg: Box::new(instantiate_msg),
+            label: "campaign".into(),
+        };
+        app.execute_contract(Addr::unchecked(OWNER), factory.clone(), &msg, &[])
+            .unwrap();
+        let res: ListCampaignsResponse = app
+            .wrap()
+            .query_wasm_smart(
+                &factory,
+                &QueryMsg::ListCampaigns {
+                    start_after: None,
+                    limit: None,
+                },
+            )
+            .unwrap();
+        let campaign = res.campaigns[0].address.clone();
+
+        let msg = campaign_msg::ExecuteMsg::Contribute {
+            project_id: CAMPAIGN_PROJECT_ID,
+            tier: None,
+            proof: None,
+            referrer: None,
+        };
+        let funds = coins(100, "ujuno");
+        app.execute_contract(Addr::unchecked("backer"), campaign.clone(), &msg, &funds)
+            .unwrap();
+        app.update_block(|block| block.time = block.time.plus_seconds(100));
+        let msg = campaign_msg::ExecuteMsg::Withdraw {
+            project_id: CAMPAIGN_PROJECT_ID,
+        };
+        app.execute_contract(Addr::unchecked(OWNER), campaign, &msg, &[])
+            .unwrap();
+
+        let balance = |addr: &str| app.wrap().query_balance(addr, "ujuno").unwrap().amount;
+        assert_eq!(balance("treasury"), Uint128::new(10));
+        assert_eq!(balance(OWNER), Uint128::new(90));
+    }
+}
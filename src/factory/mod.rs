@@ -0,0 +1,6 @@
+// Campaign factory, a companion contract which instantiates one crowd funding
+// contract per campaign and keeps track of them.
+// Build it with `--features factory`, which switches wasm exports to the factory.
+pub mod contract;
+pub mod msg;
+pub mod state;
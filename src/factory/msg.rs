@@ -0,0 +1,72 @@
+use cosmwasm_std::Addr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::factory::state::Campaign;
+use crate::msg;
+use crate::state::{PlatformFee, Status};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    // code id of crowd funding contract
+    pub code_id: u64,
+    // factory admin, defaults to the instantiating sender
+    pub admin: Option<String>,
+    // platform fee set on every campaign, callers cannot choose their own
+    #[serde(default)]
+    pub platform_fee: Option<msg::PlatformFeeMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    // anyone can start a campaign, sender becomes owner of its project and factory admin its admin.
+    // instantiate_msg must carry the project.
+    CreateCampaign {
+        instantiate_msg: Box<msg::InstantiateMsg>,
+        label: String,
+    },
+    // only admin can point the factory at new crowd funding code
+    UpdateCodeId { code_id: u64 },
+    // only admin can change the fee of campaigns created from now on
+    UpdatePlatformFee {
+        platform_fee: Option<msg::PlatformFeeMsg>,
+    },
+    // anyone can refresh indexed status and owner of a campaign from the campaign contract
+    SyncCampaign { address: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetConfig {},
+    GetCampaign {
+        address: Addr,
+    },
+    ListCampaigns {
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+    ListCampaignsByOwner {
+        owner: Addr,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+    ListCampaignsByStatus {
+        status: Status,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetConfigResponse {
+    pub admin: Addr,
+    pub code_id: u64,
+    pub platform_fee: Option<PlatformFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListCampaignsResponse {
+    pub campaigns: Vec<Campaign>,
+}
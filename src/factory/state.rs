@@ -0,0 +1,66 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+
+use crate::state::{PlatformFee, Status};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Config {
+    // admin can update code id and becomes wasm admin of every campaign
+    pub admin: Addr,
+    // code id of crowd funding contract
+    pub code_id: u64,
+    // fee every campaign pays to the platform treasury
+    #[serde(default)]
+    pub platform_fee: Option<PlatformFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Campaign {
+    pub address: Addr,
+    pub owner: Addr,
+    pub title: String,
+    // last known status of the campaign project, refreshed via SyncCampaign
+    pub status: Status,
+}
+
+// campaign being instantiated, address is known only in reply
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingCampaign {
+    pub owner: Addr,
+    pub title: String,
+}
+
+pub struct CampaignIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, Campaign, &'a Addr>,
+    pub status: MultiIndex<'a, String, Campaign, &'a Addr>,
+}
+
+impl<'a> IndexList<Campaign> for CampaignIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Campaign>> + '_> {
+        let v: Vec<&dyn Index<Campaign>> = vec![&self.owner, &self.status];
+        Box::new(v.into_iter())
+    }
+}
+
+// status index key, e.g. "Ongoing"
+pub fn status_key(status: &Status) -> String {
+    format!("{:?}", status)
+}
+
+// campaigns keyed by contract address
+pub fn campaigns<'a>() -> IndexedMap<'a, &'a Addr, Campaign, CampaignIndexes<'a>> {
+    let indexes = CampaignIndexes {
+        owner: MultiIndex::new(|c: &Campaign| c.owner.clone(), "campaigns", "campaigns__owner"),
+        status: MultiIndex::new(|c: &Campaign| status_key(&c.status), "campaigns", "campaigns__status"),
+    };
+    IndexedMap::new("campaigns", indexes)
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const PENDING_CAMPAIGN: Item<PendingCampaign> = Item::new("pending_campaign");
@@ -1,7 +1,9 @@
 pub mod contract;
+pub mod factory;
 mod error;
 // pub mod helpers;
 // pub mod integration_tests;
+pub mod migrations;
 pub mod msg;
 pub mod state;
 
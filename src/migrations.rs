@@ -0,0 +1,219 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Api, Env, Order, QuerierWrapper, StdResult, Storage, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use cw_storage_plus::{Item, Map};
+
+use crate::contract::isqrt;
+use crate::error::ContractError;
+use crate::msg::MigrateMsg;
+use crate::state::{self, Config, Status, TokenConfig, CONFIG};
+
+// v0.1.0 layout.
+// TokenConfig and CONTRIBUTIONS are the same as v0.2.0, ProjectInfo had no settlement tracking
+// and there was no contract level config.
+pub mod v0_1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub struct ProjectInfo {
+        pub title: String,
+        pub description: String,
+        pub project_owner: Addr,
+        pub target_amount: Uint128,
+        pub end_time: u64,
+        pub current_amount: Uint128,
+        // only Ongoing, Succeeded and Failed existed
+        pub status: Status,
+    }
+
+    pub const PROJECT_INFO: Item<ProjectInfo> = Item::new("project_info");
+
+    // Upgrades to v0.2.0 layout.
+    // v0.1.0 never recorded payouts, so they are rebuilt from what the contract still holds.
+    // Backers refunded back then come from msg, a withdrawal shows as a balance short of
+    // the raised amount. Any other shortfall stops the migration.
+    // cw20 contributions it credited to the token address stay where they are and
+    // can be handed out by admin via ReassignLegacyContribution.
+    pub fn migrate(
+        storage: &mut dyn Storage,
+        api: &dyn Api,
+        querier: &QuerierWrapper,
+        env: &Env,
+        msg: MigrateMsg,
+    ) -> Result<(), ContractError> {
+        let legacy = PROJECT_INFO.load(storage)?;
+        let token_config = v0_2::TOKEN_CONFIG.load(storage)?;
+
+        let mut refunds = vec![];
+        let mut refunded_amount = Uint128::zero();
+        for addr in msg.refunded {
+            let addr = api.addr_validate(&addr)?;
+            let amount = v0_2::CONTRIBUTIONS
+                .may_load(storage, &addr)?
+                .ok_or(ContractError::NoContribution {})?;
+            refunded_amount += amount;
+            refunds.push((addr, amount));
+        }
+
+        let balance = match &token_config {
+            TokenConfig::Native { denom } => {
+                querier.query_balance(&env.contract.address, denom)?.amount
+            }
+            TokenConfig::CW20 { addr } => {
+                let res: BalanceResponse = querier.query_wasm_smart(
+                    addr,
+                    &Cw20QueryMsg::Balance {
+                        address: env.contract.address.to_string(),
+                    },
+                )?;
+                res.balance
+            }
+        };
+
+        let mut status = legacy.status;
+        let mut withdrawn_amount = Uint128::zero();
+        let owed = legacy.current_amount.saturating_sub(refunded_amount);
+        if balance < owed {
+            // v0.1.0 withdraw paid out everything raised, and could be repeated
+            let withdrawable =
+                status == Status::Succeeded && legacy.end_time <= env.block.time.seconds();
+            if !withdrawable || !refunded_amount.is_zero() {
+                return Err(ContractError::LegacyBalanceShort { owed, balance });
+            }
+            withdrawn_amount = legacy.current_amount;
+            status = Status::Withdrawn;
+        } else if !refunded_amount.is_zero() {
+            status = Status::Refunding;
+        }
+
+        for (addr, amount) in refunds {
+            v0_2::CONTRIBUTIONS.remove(storage, &addr);
+            v0_2::REFUNDS.save(storage, &addr, &amount)?;
+        }
+
+        let admin = match msg.admin {
+            Some(admin) => api.addr_validate(&admin)?,
+            None => legacy.project_owner.clone(),
+        };
+        CONFIG.save(
+            storage,
+            &Config {
+                admin,
+                platform_fee: None,
+            },
+        )?;
+
+        let project_info = v0_2::ProjectInfo {
+            title: legacy.title,
+            description: legacy.description,
+            project_owner: legacy.project_owner,
+            target_amount: legacy.target_amount,
+            end_time: legacy.end_time,
+            current_amount: legacy.current_amount,
+            withdrawn_amount,
+            refunded_amount,
+            status,
+        };
+        v0_2::PROJECT_INFO.save(storage, &project_info)?;
+
+        Ok(())
+    }
+}
+
+// v0.2.0 layout.
+// A contract instance held exactly one project in singletons.
+pub mod v0_2 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub struct ProjectInfo {
+        pub title: String,
+        pub description: String,
+        pub project_owner: Addr,
+        pub target_amount: Uint128,
+        pub end_time: u64,
+        pub current_amount: Uint128,
+        #[serde(default)]
+        pub withdrawn_amount: Uint128,
+        #[serde(default)]
+        pub refunded_amount: Uint128,
+        pub status: Status,
+    }
+
+    pub const TOKEN_CONFIG: Item<TokenConfig> = Item::new("token_config");
+    pub const PROJECT_INFO: Item<ProjectInfo> = Item::new("project_info");
+    pub const CONTRIBUTIONS: Map<&Addr, Uint128> = Map::new("contributions");
+    pub const REFUNDS: Map<&Addr, Uint128> = Map::new("refunds");
+
+    // id the single project gets in multi-project layout
+    pub const PROJECT_ID: u64 = 1;
+
+    // Upgrades to v0.3.0 layout, the single project becomes project 1.
+    pub fn migrate(storage: &mut dyn Storage) -> Result<(), ContractError> {
+        let legacy = PROJECT_INFO.load(storage)?;
+        let token_config = TOKEN_CONFIG.load(storage)?;
+
+        let project_info = state::ProjectInfo {
+            title: legacy.title,
+            description: legacy.description,
+            project_owner: legacy.project_owner,
+            target_amount: legacy.target_amount,
+            max_amount: None,
+            min_contribution: None,
+            max_contribution_per_address: None,
+            unpledge_penalty: 0,
+            referral_reward: 0,
+            start_time: None,
+            end_time: legacy.end_time,
+            current_amount: legacy.current_amount,
+            withdrawn_amount: legacy.withdrawn_amount,
+            fee_amount: Uint128::zero(),
+            refunded_amount: legacy.refunded_amount,
+            status: legacy.status,
+        };
+        state::PROJECT_COUNT.save(storage, &PROJECT_ID)?;
+        state::PROJECTS.save(storage, PROJECT_ID, &project_info)?;
+        state::TOKEN_CONFIGS.save(storage, PROJECT_ID, &token_config)?;
+        PROJECT_INFO.remove(storage);
+        TOKEN_CONFIG.remove(storage);
+
+        // cw20 contributions credited to the token address are not a contributor,
+        // zero entries are dropped
+        let legacy_addr = match token_config {
+            TokenConfig::CW20 { addr } => Some(addr),
+            TokenConfig::Native { denom: _ } => None,
+        };
+        let contributions = CONTRIBUTIONS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut count = 0u64;
+        let mut sqrt_sum = Uint128::zero();
+        for (addr, amount) in contributions {
+            CONTRIBUTIONS.remove(storage, &addr);
+            if amount.is_zero() {
+                continue;
+            }
+            if legacy_addr.as_ref() != Some(&addr) {
+                count += 1;
+                sqrt_sum += Uint128::new(isqrt(amount.u128()));
+            }
+            state::CONTRIBUTIONS.save(storage, (PROJECT_ID, &addr), &amount)?;
+        }
+        state::CONTRIBUTOR_COUNT.save(storage, PROJECT_ID, &count)?;
+        state::SQRT_SUMS.save(storage, PROJECT_ID, &sqrt_sum)?;
+
+        let refunds = REFUNDS
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (addr, amount) in refunds {
+            state::REFUNDS.save(storage, (PROJECT_ID, &addr), &amount)?;
+            REFUNDS.remove(storage, &addr);
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
-use crate::state::Status;
-use cosmwasm_std::{Addr, Uint128};
+use crate::state::{
+    Beneficiary, Milestone, PendingOwner, ProjectMatch, ProposalStatus, RoundStatus, Status,
+};
+use cosmwasm_std::{Addr, Decimal, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cw20::Cw20ReceiveMsg;
@@ -16,6 +18,25 @@ pub enum Token {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
+    // contract admin, defaults to the instantiating sender
+    pub admin: Option<String>,
+    // first project, more projects can be added via CreateProject
+    pub project: Option<ProjectMsg>,
+    // owner of the first project, defaults to the instantiating sender
+    pub project_owner: Option<String>,
+    // platform cut of every payout to project owners
+    pub platform_fee: Option<PlatformFeeMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformFeeMsg {
+    pub treasury: String,
+    // share of payouts sent to treasury, e.g. "0.025" for 2.5%
+    pub fee: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProjectMsg {
     // project title
     pub title: String,
     // project description
@@ -26,48 +47,499 @@ pub struct InstantiateMsg {
     // pub project_owner: Addr,
     // target amount project owner want to raise
     pub target_amount: Uint128,
+    // optional hard cap, contributions over it are sent back and the project closes early
+    pub max_amount: Option<Uint128>,
+    // contribution limits, checked against total contributed by the address
+    pub min_contribution: Option<Uint128>,
+    pub max_contribution_per_address: Option<Uint128>,
+    // percentage (0-100) of an unpledged amount kept for the project instead of the contributor,
+    // paid out at once to treasury and beneficiaries like the raised funds
+    #[serde(default)]
+    pub unpledge_penalty: u64,
+    // percentage (0-100) of owner payouts on referred contributions paid to the referrers
+    #[serde(default)]
+    pub referral_reward: u64,
+    // announced projects open for contributions at start_time (in seconds since epoch),
+    // right away when not given
+    pub start_time: Option<u64>,
     /// When end time (in seconds since epoch 00:00:00 UTC on 1 January 1970) is set and
     /// block time exceeds this value, the crowd funding is Failed.
     /// Once an project is Failed, raised amount coins can be returned to the original funder (via "refund").
     pub end_time: u64,
+    // when set, raised funds are released tranche by tranche via ReleaseMilestone
+    // instead of all at once via Withdraw
+    #[serde(default)]
+    pub milestones: Vec<MilestoneMsg>,
+    // reward levels backers can pick when contributing
+    #[serde(default)]
+    pub reward_tiers: Vec<RewardTierMsg>,
+    // hex encoded merkle root of the allowlist, only listed addresses can contribute when set.
+    // leaves are sha256 of 0x00 followed by the address, or by address and cap as "wasm1...:1000",
+    // inner nodes are sha256 of 0x01 followed by both children in sorted order
+    pub merkle_root: Option<String>,
+    // other tokens accepted besides the main token, each once, credited at a fixed rate in main
+    // token units
+    #[serde(default)]
+    pub accepted_assets: Vec<AcceptedAssetMsg>,
+    // cw721 contract to mint a receipt for every contribution, this contract must be its minter.
+    // Refunds then go to receipt holders instead of original contributors.
+    pub receipt_nft: Option<String>,
+    // when set, withdrawn funds unlock to project owner over time and are taken via ClaimVested
+    pub vesting: Option<VestingMsg>,
+    // when set, raised funds are released by proposals backers vote on instead of via Withdraw
+    pub governance: Option<GovernanceMsg>,
+    // team members sharing owner payouts, project owner gets them all when empty
+    #[serde(default)]
+    pub beneficiaries: Vec<BeneficiaryMsg>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BeneficiaryMsg {
+    pub address: String,
+    // percentage of owner payouts, beneficiaries must add up to 100
+    pub percentage: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GovernanceMsg {
+    // share of raised amount that must vote for a proposal to count, e.g. "0.3"
+    pub quorum: Decimal,
+    // share of cast votes in favor a proposal needs to pass, e.g. "0.5"
+    pub threshold: Decimal,
+    // seconds backers can vote after a proposal is created
+    pub voting_period: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingMsg {
+    // seconds after withdrawal before anything unlocks
+    pub cliff: u64,
+    // seconds after withdrawal until everything is unlocked, linearly from withdrawal
+    pub duration: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AcceptedAssetMsg {
+    pub token: Token,
+    // main token units credited per unit of this token
+    pub rate: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MilestoneMsg {
+    pub title: String,
+    // percentage of raised funds, milestones of a project must add up to 100
+    pub percentage: u64,
+    // milestone cannot be released before this time (seconds since epoch)
+    pub unlock_time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardTierMsg {
+    pub name: String,
+    // total contribution of a backer needed for this tier
+    pub min_amount: Uint128,
+    // how many backers can hold this tier, unlimited when not set
+    pub max_backers: Option<u32>,
+}
+
+// extension of minted receipt tokens
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReceiptMetadata {
+    pub project_id: u64,
+    pub amount: Uint128,
+    pub tier: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    // contract admin to set when migrating from a version without one,
+    // defaults to the project owner
+    pub admin: Option<String>,
+    // backers already refunded when migrating from v0.1.0, which kept no record of them
+    #[serde(default)]
+    pub refunded: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    // anyone can contribute coins to a project with native token
-    Contribute {},
-    // anyone can contribute coins to a project with cw20
+    // anyone can start a new project, sender becomes project owner
+    CreateProject(Box<ProjectMsg>),
+    // anyone can contribute coins to a project with native token,
+    // optionally picking a reward tier by index.
+    // allowlisted projects need a merkle proof of the sender.
+    // referrer is credited with the whole contribution of the backer, backers stay with their first
+    // referrer
+    Contribute {
+        project_id: u64,
+        tier: Option<u32>,
+        proof: Option<AllowlistProof>,
+        referrer: Option<String>,
+    },
+    // anyone can contribute coins to a project with cw20, msg is ReceiveMsg
     Receive(Cw20ReceiveMsg),
+    // only project owner can offer the project to a new owner, the offer expires at expires
+    // (seconds since epoch), a week from now when not given. A new offer replaces the old one
+    ProposeNewOwner {
+        project_id: u64,
+        addr: String,
+        expires: Option<u64>,
+    },
+    // only the proposed owner can take over the project before the offer expires
+    AcceptOwnership { project_id: u64 },
+    // only project owner can withdraw an offer not accepted yet
+    CancelOwnershipProposal { project_id: u64 },
+    // only project owner can change beneficiaries before the project closes,
+    // an empty list pays the project owner again
+    UpdateBeneficiaries {
+        project_id: u64,
+        beneficiaries: Vec<BeneficiaryMsg>,
+    },
     // only project owner can withdraw raised funds
-    Withdraw {},
+    Withdraw { project_id: u64 },
+    // only project owner of a succeeded project with governance can propose releasing funds
+    CreateProposal {
+        project_id: u64,
+        amount: Uint128,
+        purpose: String,
+    },
+    // backers vote with the weight of their contribution, once per proposal.
+    // in receipt projects holders vote with the receipts they hold instead.
+    // the project owner never votes
+    Vote {
+        project_id: u64,
+        proposal_id: u64,
+        approve: bool,
+        #[serde(default)]
+        receipts: Vec<String>,
+    },
+    // anyone can settle a proposal after voting ended,
+    // funds are paid out when it passed and stay escrowed otherwise
+    ExecuteProposal { project_id: u64, proposal_id: u64 },
+    // only project owner can claim what unlocked so far of vested withdrawal
+    ClaimVested { project_id: u64 },
+    // only project owner can release a milestone of a succeeded project
+    ReleaseMilestone { project_id: u64, index: u32 },
+    // project owner or admin can give up a succeeded project,
+    // contributors then get back funds not released yet pro-rata
+    Abandon { project_id: u64 },
+    // project owner or admin can abort a project before anything is paid out,
+    // contributors can refund right away
+    Cancel { project_id: u64 },
+    // sponsor escrows main token of a project to match contributions at ratio
+    // until the deposit runs out, the same sponsor can top up and change the ratio
+    DepositMatchingFunds { project_id: u64, ratio: Decimal },
+    // sponsor takes back matching funds not used once the project closed,
+    // all of them when the project failed
+    ReclaimMatchingFunds { project_id: u64 },
+    // only admin can start a quadratic funding round over projects with the same main token
+    // that end by the round end_time, attestor optionally vouches for contributors who count
+    CreateRound {
+        token: Token,
+        project_ids: Vec<u64>,
+        attestor: Option<String>,
+        end_time: u64,
+    },
+    // anyone can add native token to the matching pool of an open round,
+    // cw20 rounds are funded through Receive
+    FundRound { round_id: u64 },
+    // only round attestor can vouch for contributors, e.g. after a sybil check
+    AttestContributors { round_id: u64, addresses: Vec<String> },
+    // only admin can fix matches once the round ended
+    TallyRound { round_id: u64 },
+    // only admin can credit tallied matches to the projects as matched funds, dust goes to admin
+    DistributeRound { round_id: u64 },
+    // only admin can call off a round before distribution, its projects are free to pay out
+    CancelRound { round_id: u64 },
+    // funders of a cancelled round take back what they put into its matching pool
+    ReclaimRoundFunds { round_id: u64 },
+    // contributors can take back part or all of their contribution before the project closes,
+    // minus the unpledge penalty. amount is in units of the asset, main token when not given
+    Unpledge {
+        project_id: u64,
+        amount: Uint128,
+        asset: Option<u32>,
+    },
     // contributors can execute refund after the end_time
     // if the raised amount didn't satisfy target amount before end_time
-    Refund {},
+    // or the project has been abandoned.
+    // projects with receipt nft refund by receipt token id to its current holder
+    Refund {
+        project_id: u64,
+        receipt: Option<String>,
+    },
+    // only admin can move cw20 contributions which older versions of this contract
+    // recorded under the cw20 token address to the real contributor
+    ReassignLegacyContribution {
+        project_id: u64,
+        recipient: String,
+        amount: Uint128,
+    },
+}
+
+// msg field of Cw20ReceiveMsg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Contribute {
+        project_id: u64,
+        tier: Option<u32>,
+        proof: Option<AllowlistProof>,
+        referrer: Option<String>,
+    },
+    DepositMatchingFunds {
+        project_id: u64,
+        ratio: Decimal,
+    },
+    FundRound {
+        round_id: u64,
+    },
+}
+
+// merkle proof that an address is on the allowlist of a project
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowlistProof {
+    // hex encoded sibling hashes from the leaf up to the root
+    pub proof: Vec<String>,
+    // per address cap encoded in the leaf, if any
+    pub cap: Option<Uint128>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetProjectInfo {},
-    GetContribution { address: Addr },
+    GetProjectInfo { project_id: u64 },
+    ListProjects {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetContribution { project_id: u64, address: Addr },
+    // backers of a project in address order
+    ListContributions {
+        project_id: u64,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+    GetContributorCount { project_id: u64 },
+    // how much more an address may contribute under the contribution limits
+    GetContributionAllowance { project_id: u64, address: Addr },
+    // reward tiers of a project with how many are left
+    GetRewardTiers { project_id: u64 },
+    GetReceipt { project_id: u64, token_id: String },
+    GetReferral { project_id: u64, referrer: Addr },
+    // referrers who brought in the most, best first
+    ListTopReferrers { project_id: u64, limit: Option<u32> },
+    GetMatchingPool { project_id: u64 },
+    GetVesting { project_id: u64 },
+    GetProposal { project_id: u64, proposal_id: u64 },
+    ListProposals {
+        project_id: u64,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    GetRound { round_id: u64 },
+    // match every project of a round would get if it was tallied now
+    EstimateRoundMatches { round_id: u64 },
+    // cw20 contributions recorded under the cw20 token address by older versions
+    GetLegacyContribution { project_id: u64 },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetProjectInfoResponse {
+    pub project_id: u64,
     pub title: String,
     pub description: String,
     pub project_owner: Addr,
     pub token: Token,
     pub target_amount: Uint128,
+    pub max_amount: Option<Uint128>,
+    pub min_contribution: Option<Uint128>,
+    pub max_contribution_per_address: Option<Uint128>,
+    pub unpledge_penalty: u64,
+    pub referral_reward: u64,
+    pub start_time: Option<u64>,
+    // seconds left until start_time while the project is upcoming
+    pub starts_in: Option<u64>,
     pub end_time: u64,
 
     pub current_amount: Uint128,
+    pub withdrawn_amount: Uint128,
+    pub refunded_amount: Uint128,
     pub status: Status,
+    pub milestones: Vec<Milestone>,
+    // platform fee taken from payouts and what of withdrawn amount went to treasury
+    pub platform_fee: Decimal,
+    pub fee_amount: Uint128,
+    pub receipt_nft: Option<Addr>,
+    pub merkle_root: Option<String>,
+    // ownership transfer waiting for acceptance
+    pub pending_owner: Option<PendingOwner>,
+    // share owner payouts, empty when all goes to project owner
+    pub beneficiaries: Vec<Beneficiary>,
+    // every accepted token, main token first
+    pub assets: Vec<AssetResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AssetResponse {
+    pub token: Token,
+    pub rate: Decimal,
+    // raised amount in units of the token
+    pub raised: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenAmount {
+    pub token: Token,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListProjectsResponse {
+    pub projects: Vec<GetProjectInfoResponse>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GetContributionResponse {
     pub token: Token,
+    // amount still held by the contract, in main token units
+    pub amount: Uint128,
+    // amount already returned through refund
+    pub refunded_amount: Uint128,
+    // index of reward tier the backer picked, in receipt projects each receipt carries its own
+    pub tier: Option<u32>,
+    // amount still held by the contract per token, main token first
+    pub assets: Vec<TokenAmount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetContributionAllowanceResponse {
+    pub contributed: Uint128,
+    // smallest amount accepted with the next contribution
+    pub min_amount: Uint128,
+    // None when there is no per address maximum
+    pub remaining: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralResponse {
+    pub referrer: Addr,
+    // current contributions of referred backers
+    pub amount: Uint128,
+    // referral rewards paid so far
+    pub rewarded: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContributionResponse {
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListContributionsResponse {
+    pub contributions: Vec<ContributionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContributorCountResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalResponse {
+    pub proposal_id: u64,
+    pub amount: Uint128,
+    pub purpose: String,
+    pub end_time: u64,
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListProposalsResponse {
+    pub proposals: Vec<ProposalResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingResponse {
+    pub cliff: u64,
+    pub duration: u64,
+    // set once raised funds are withdrawn
+    pub start_time: Option<u64>,
+    pub amount: Uint128,
+    pub claimed: Uint128,
+    // unlocked and not claimed yet
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MatchingPoolResponse {
+    pub sponsor: Addr,
+    pub ratio: Decimal,
+    pub cap: Uint128,
+    pub matched: Uint128,
+    // cap not matched yet
+    pub remaining: Uint128,
+    pub reclaimed: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundResponse {
+    pub round_id: u64,
+    pub token: Token,
+    pub project_ids: Vec<u64>,
+    pub attestor: Option<Addr>,
+    pub end_time: u64,
+    pub matching_pool: Uint128,
+    pub status: RoundStatus,
+    // empty until the round is tallied
+    pub matches: Vec<ProjectMatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoundMatchesResponse {
+    pub matches: Vec<ProjectMatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListReferralsResponse {
+    pub referrals: Vec<ReferralResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetReceiptResponse {
+    pub token_id: String,
+    // contributor the receipt was minted to
+    pub backer: Addr,
+    // current holder, who owns refund and reward rights
+    pub holder: Addr,
+    pub amount: Uint128,
+    pub tier: Option<u32>,
+    pub refunded: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardTierResponse {
+    pub index: u32,
+    pub name: String,
+    pub min_amount: Uint128,
+    pub max_backers: Option<u32>,
+    pub backers: u32,
+    // None when the tier is unlimited
+    pub available: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetRewardTiersResponse {
+    pub tiers: Vec<RewardTierResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GetLegacyContributionResponse {
+    pub token: Token,
+    // amount still waiting to be reassigned to the real contributors
     pub amount: Uint128,
 }
@@ -1,8 +1,27 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Decimal, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+// Contract level configuration
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Config {
+    // admin can fix up contribution records, e.g. legacy cw20 balances
+    pub admin: Addr,
+    // platform cut of every payout to project owners
+    #[serde(default)]
+    pub platform_fee: Option<PlatformFee>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PlatformFee {
+    pub treasury: Addr,
+    // share of payouts sent to treasury, at most 1
+    pub fee: Decimal,
+}
 
 // Token config is immutable once contract created
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -14,6 +33,24 @@ pub enum TokenConfig {
     CW20 { addr: Addr },
 }
 
+// Token a project accepts besides its main token.
+// Main token is the accounting unit, amounts of other tokens are credited at a fixed rate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AcceptedAsset {
+    pub token: TokenConfig,
+    // main token units credited per unit of this token
+    pub rate: Decimal,
+}
+
+// Amount of an accepted asset, in units of the asset and as credited in main token units
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AssetAmount {
+    pub amount: Uint128,
+    pub credited: Uint128,
+}
+
 // Potentially mutable data, depends on the crowdfunding specification
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -23,21 +60,312 @@ pub struct ProjectInfo {
     pub project_owner: Addr,
     // target amount of token
     pub target_amount: Uint128,
+    // hard cap, project closes early once reached
+    #[serde(default)]
+    pub max_amount: Option<Uint128>,
+    // smallest total a contributor may have in the project
+    #[serde(default)]
+    pub min_contribution: Option<Uint128>,
+    // largest total a contributor may have in the project
+    #[serde(default)]
+    pub max_contribution_per_address: Option<Uint128>,
+    // percentage kept from unpledged amounts, paid out like the raised funds
+    #[serde(default)]
+    pub unpledge_penalty: u64,
+    // percentage of owner payouts on referred contributions paid to referrers
+    #[serde(default)]
+    pub referral_reward: u64,
+    // when crowd funding project starts taking contributions, right away when None
+    #[serde(default)]
+    pub start_time: Option<u64>,
     // when crowd funding project ends
     pub end_time: u64,
 
     // current amout of denom token contributed
     pub current_amount: Uint128,
+    // amount already paid out to project owner, including platform fee
+    #[serde(default)]
+    pub withdrawn_amount: Uint128,
+    // part of withdrawn amount paid to platform treasury
+    #[serde(default)]
+    pub fee_amount: Uint128,
+    // amount already returned to contributors
+    #[serde(default)]
+    pub refunded_amount: Uint128,
     pub status: Status,
 }
 
+// Tranche of raised funds project owner can release once unlock_time has passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Milestone {
+    pub title: String,
+    // percentage of raised funds, milestones of a project add up to 100
+    pub percentage: u64,
+    pub unlock_time: u64,
+    pub released: bool,
+}
+
+// Reward level backers pick when contributing
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RewardTier {
+    pub name: String,
+    // total contribution of a backer needed for this tier
+    pub min_amount: Uint128,
+    // None means unlimited
+    pub max_backers: Option<u32>,
+    // number of backers currently holding this tier
+    pub backers: u32,
+}
+
+// Contribution receipt, minted as cw721 token when project has a receipt nft contract.
+// Refund right belongs to whoever holds the token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Receipt {
+    // contributor the receipt was minted to
+    pub backer: Addr,
+    pub amount: Uint128,
+    pub tier: Option<u32>,
+    pub refunded: bool,
+}
+
+// What a referrer brought into a project
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Referral {
+    pub project_id: u64,
+    pub referrer: Addr,
+    // current contributions of backers who came with this referrer
+    pub amount: Uint128,
+    // referral rewards paid out so far
+    pub rewarded: Uint128,
+}
+
+// Owner share of a withdrawal unlocking over time, cliff then linear
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Vesting {
+    pub cliff: u64,
+    pub duration: u64,
+    // time of withdrawal, vesting starts then
+    pub start_time: Option<u64>,
+    // paid out amount at withdrawal, owner share follows right after it
+    pub offset: Uint128,
+    // owner share of the withdrawal
+    pub amount: Uint128,
+    pub claimed: Uint128,
+}
+
+// Team member receiving a share of what is paid out to the project owner
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Beneficiary {
+    pub address: Addr,
+    // percentage of owner payouts, beneficiaries of a project add up to 100
+    pub percentage: u64,
+}
+
+// Address the project owner offered the project to, it takes over once it accepts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingOwner {
+    pub owner: Addr,
+    // offer can't be accepted from this time on (seconds since epoch)
+    pub expires: u64,
+}
+
+// Voting rules of a project releasing funds by proposal
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Governance {
+    // share of raised amount that must vote
+    pub quorum: Decimal,
+    // share of cast votes in favor needed to pass
+    pub threshold: Decimal,
+    pub voting_period: u64,
+}
+
+// Request of project owner to release part of raised funds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Proposal {
+    pub amount: Uint128,
+    pub purpose: String,
+    // voting closes at this time
+    pub end_time: u64,
+    // contribution weight voted in favor and against
+    pub yes: Uint128,
+    pub no: Uint128,
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum ProposalStatus {
+    Open,
+    // passed and paid out
+    Executed,
+    // missed quorum or threshold, funds stay escrowed
+    Rejected,
+}
+
+// Sponsor funds escrowed in main token to match backer contributions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MatchingPool {
+    pub sponsor: Addr,
+    // matched amount per contributed amount, 1 matches one to one
+    pub ratio: Decimal,
+    // deposited funds, no more than this gets matched
+    pub cap: Uint128,
+    // part of cap added to the project so far
+    pub matched: Uint128,
+    // amount already returned to the sponsor
+    pub reclaimed: Uint128,
+}
+
+// Quadratic funding round, matching pool is split across its projects
+// in proportion to the square of the sum of square roots of contributions
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Round {
+    // matching pool token, main token of every project in the round
+    pub token: TokenConfig,
+    pub project_ids: Vec<u64>,
+    // only contributors this address attested count when set
+    pub attestor: Option<Addr>,
+    // every project of the round ends by then
+    pub end_time: u64,
+    pub matching_pool: Uint128,
+    pub status: RoundStatus,
+    // match of every project, set by the tally
+    pub matches: Vec<ProjectMatch>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProjectMatch {
+    pub project_id: u64,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub enum RoundStatus {
+    // taking matching funds and attestations
+    Open,
+    // matches are fixed, waiting for distribution
+    Tallied,
+    // matches credited to the projects as matched funds
+    Distributed,
+    // called off, funders take their funds back
+    Cancelled,
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub enum Status {
+    // start_time not reached yet, contributions are not accepted
+    Upcoming,
     Ongoing,
     Succeeded,
     Failed,
+    // raised funds have been paid out to project owner
+    Withdrawn,
+    // project failed and contributors started claiming refunds
+    Refunding,
+    // project succeeded but owner or admin gave up on it,
+    // funds not released yet are refundable pro-rata
+    Abandoned,
+    // owner or admin aborted the project before any payout, contributions are refundable
+    Cancelled,
 }
 
-pub const TOKEN_CONFIG: Item<TokenConfig> = Item::new("token_config");
-pub const PROJECT_INFO: Item<ProjectInfo> = Item::new("project_info");
-pub const CONTRIBUTIONS: Map<&Addr, Uint128> = Map::new("contributions");
+pub const CONFIG: Item<Config> = Item::new("config");
+// number of projects created so far, also the id of the latest project
+pub const PROJECT_COUNT: Item<u64> = Item::new("project_count");
+pub const TOKEN_CONFIGS: Map<u64, TokenConfig> = Map::new("token_configs");
+// accepted assets besides the main token, asset index 0 is the main token, these follow from 1
+pub const ACCEPTED_ASSETS: Map<u64, Vec<AcceptedAsset>> = Map::new("accepted_assets");
+// (project id, asset index) -> raised amount of an accepted asset.
+// main token part is whatever of current_amount is not credited by other assets
+pub const ASSETS_RAISED: Map<(u64, u32), AssetAmount> = Map::new("assets_raised");
+// (project id, contributor, asset index) -> contributed amount of an accepted asset
+pub const ASSET_CONTRIBUTIONS: Map<(u64, &Addr, u32), AssetAmount> =
+    Map::new("asset_contributions");
+pub const PROJECTS: Map<u64, ProjectInfo> = Map::new("projects");
+// projects without beneficiaries pay the project owner
+pub const BENEFICIARIES: Map<u64, Vec<Beneficiary>> = Map::new("beneficiaries");
+// ownership transfers waiting for the new owner to accept
+pub const PENDING_OWNERS: Map<u64, PendingOwner> = Map::new("pending_owners");
+// only projects releasing funds tranche by tranche have milestones
+pub const MILESTONES: Map<u64, Vec<Milestone>> = Map::new("milestones");
+// only projects releasing funds by proposal have governance
+pub const GOVERNANCES: Map<u64, Governance> = Map::new("governances");
+// (project id, proposal id) -> proposal, ids count up from 1 per project
+pub const PROPOSALS: Map<(u64, u64), Proposal> = Map::new("proposals");
+pub const PROPOSAL_COUNT: Map<u64, u64> = Map::new("proposal_count");
+// (project id, proposal id, voter) -> whether the backer approved
+pub const VOTES: Map<(u64, u64, &Addr), bool> = Map::new("votes");
+// (project id, proposal id, receipt token id) -> whether the holder approved, receipt projects only
+pub const RECEIPT_VOTES: Map<(u64, u64, &str), bool> = Map::new("receipt_votes");
+// only projects paying project owner out over time have vesting
+pub const VESTINGS: Map<u64, Vesting> = Map::new("vestings");
+pub const REWARD_TIERS: Map<u64, Vec<RewardTier>> = Map::new("reward_tiers");
+// cw721 contract minting receipts of a project, this contract must be its minter
+pub const RECEIPT_NFTS: Map<u64, Addr> = Map::new("receipt_nfts");
+// number of receipts minted per project, token ids are "{project_id}-{number}"
+pub const RECEIPT_COUNT: Map<u64, u64> = Map::new("receipt_count");
+// hex encoded sha256 merkle root of addresses allowed to contribute to a private project
+pub const MERKLE_ROOTS: Map<u64, String> = Map::new("merkle_roots");
+// (project id, token id) -> receipt
+pub const RECEIPTS: Map<(u64, &str), Receipt> = Map::new("receipts");
+// (project id, contributor) -> contributed amount
+pub const CONTRIBUTIONS: Map<(u64, &Addr), Uint128> = Map::new("project_contributions");
+// number of addresses with a contribution recorded, kept in step with CONTRIBUTIONS
+pub const CONTRIBUTOR_COUNT: Map<u64, u64> = Map::new("contributor_count");
+// (project id, contributor) -> index of reward tier the backer picked
+pub const BACKER_TIERS: Map<(u64, &Addr), u32> = Map::new("backer_tiers");
+// contributors who already got their contribution back, with refunded amount
+pub const REFUNDS: Map<(u64, &Addr), Uint128> = Map::new("project_refunds");
+// (project id, contributor) -> referrer, backers stay with the referrer they first came with
+pub const REFERRERS: Map<(u64, &Addr), Addr> = Map::new("referrers");
+pub const MATCHING_POOLS: Map<u64, MatchingPool> = Map::new("matching_pools");
+// number of quadratic funding rounds created so far, also the id of the latest round
+pub const ROUND_COUNT: Item<u64> = Item::new("round_count");
+pub const ROUNDS: Map<u64, Round> = Map::new("rounds");
+// (round id, contributor) of contributors the round attestor vouched for
+pub const ATTESTATIONS: Map<(u64, &Addr), bool> = Map::new("attestations");
+// (round id, funder) -> matching funds a funder put into the round
+pub const ROUND_FUNDS: Map<(u64, &Addr), Uint128> = Map::new("round_funds");
+// project id -> sum of square roots of every contribution, kept up to date for rounds
+pub const SQRT_SUMS: Map<u64, Uint128> = Map::new("sqrt_sums");
+// (project id, round id) -> sum of square roots of attested contributions.
+// every project has an entry for each round it is in until the round is distributed
+pub const ROUND_SQRT_SUMS: Map<(u64, u64), Uint128> = Map::new("round_sqrt_sums");
+// project id -> round matches credited to the project
+pub const ROUND_MATCHED: Map<u64, Uint128> = Map::new("round_matched");
+// (project id, contributor) -> sponsor funds matched to the contributions of a backer
+pub const MATCHED: Map<(u64, &Addr), Uint128> = Map::new("matched");
+
+pub struct ReferralIndexes<'a> {
+    pub amount: MultiIndex<'a, (u64, u128), Referral, (u64, &'a Addr)>,
+}
+
+impl<'a> IndexList<Referral> for ReferralIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Referral>> + '_> {
+        let v: Vec<&dyn Index<Referral>> = vec![&self.amount];
+        Box::new(v.into_iter())
+    }
+}
+
+// referrals keyed by (project id, referrer), indexed by amount for leaderboards
+pub fn referrals<'a>() -> IndexedMap<'a, (u64, &'a Addr), Referral, ReferralIndexes<'a>> {
+    let indexes = ReferralIndexes {
+        amount: MultiIndex::new(
+            |r: &Referral| (r.project_id, r.amount.u128()),
+            "referrals",
+            "referrals__amount",
+        ),
+    };
+    IndexedMap::new("referrals", indexes)
+}